@@ -1,42 +1,168 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::str::FromStr;
+
 use super::skolem::SkolemContext;
-use crate::types::ast::Expression;
+use crate::types::ast::{Expression, Proposition, Term};
 use crate::types::clause::{Clause, Literal, Program};
 use crate::types::{GicError, Result};
 
+/// How a quantifier-free formula is turned into clauses.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum CnfMode {
+	/// Distribute disjunctions over conjunctions. The clauses are equivalent to the formula, and a
+	/// Horn formula gives Horn clauses, but a disjunction of conjunctions gives exponentially many
+	/// clauses.
+	#[default]
+	Distributive,
+	/// Name each conjunction under a disjunction with a fresh definition predicate, like `_def1`,
+	/// implied by the predicate. The clauses grow linearly with the formula, and are satisfiable
+	/// exactly when the formula is, but may not be Horn.
+	Definitional,
+}
+
+impl fmt::Display for CnfMode {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			CnfMode::Distributive => write!(f, "distributive"),
+			CnfMode::Definitional => write!(f, "definitional"),
+		}
+	}
+}
+
+impl FromStr for CnfMode {
+	type Err = String;
+
+	fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+		match s.to_ascii_lowercase().as_str() {
+			"distributive" => Ok(CnfMode::Distributive),
+			"definitional" => Ok(CnfMode::Definitional),
+			_ => Err(format!("Invalid CNF mode: '{}'", s)),
+		}
+	}
+}
+
+/// The prefix of the definition predicates introduced by [`CnfMode::Definitional`]. Predicates
+/// written by the user start with an uppercase letter, so they never clash.
+pub const DEFINITION_PREFIX: &str = "_def";
+
 pub struct Clausifier {
-	clause_id: usize,
 	ctx: SkolemContext,
 	program: Program,
+	mode: CnfMode,
+	/// The number of definition predicates introduced so far.
+	definitions: usize,
+}
+
+impl Default for Clausifier {
+	fn default() -> Self {
+		Self::new()
+	}
 }
 
 impl Clausifier {
 	pub fn new() -> Self {
-		Clausifier { clause_id: 1, ctx: SkolemContext::new(), program: Program(Vec::new()) }
+		Clausifier {
+			ctx: SkolemContext::new(),
+			program: Program::new(),
+			mode: CnfMode::default(),
+			definitions: 0,
+		}
+	}
+
+	/// Turns formulas into clauses with `mode` instead of the default distributive CNF.
+	pub fn with_mode(mut self, mode: CnfMode) -> Self {
+		self.mode = mode;
+		self
 	}
 
 	pub fn add_to_program(&mut self, expr: Expression) -> Result<()> {
 		let programified_clause = self.clausify(expr)?;
-		for c in programified_clause.0.iter() {
-			self.program.0.push(c.clone());
-		}
+		self.add_clauses(programified_clause);
 		Ok(())
 	}
 
-	pub fn clausify(&mut self, expr: Expression) -> Result<Program> {
-		self.ctx.set_clause_id(self.clause_id);
-		self.clause_id += 1;
+	/// Adds clauses returned by [`clausify`](Clausifier::clausify) to the program.
+	pub fn add_clauses(&mut self, clauses: Program) {
+		for c in clauses {
+			self.program.push(c);
+		}
+	}
 
+	pub fn clausify(&mut self, expr: Expression) -> Result<Program> {
 		let no_implications = eliminate_implications(expr);
 		let nnf = to_nnf(no_implications);
-		let prenex = distribute_quantifiers(nnf);
+		let renamed = standardize_apart(nnf);
+		let miniscoped = miniscope(renamed);
 
-		let no_existentials = self.ctx.deskolem(prenex);
+		let no_existentials = self.ctx.deskolem(miniscoped);
 		let quantifier_free = remove_universal_quantifiers(no_existentials);
-		let cnf = flatten_cnf(quantifier_free)?;
+		let cnf = match self.mode {
+			CnfMode::Distributive => flatten_cnf(quantifier_free)?,
+			CnfMode::Definitional => self.definitional_cnf(quantifier_free)?,
+		};
 
 		Ok(cnf)
 	}
 
+	/// Converts a quantifier-free formula in negation normal form to clauses, naming each
+	/// conjunction `C` under a disjunction with a fresh predicate `D` over the variables of `C`.
+	/// Only `D ⇒ C` is added, since `D` only occurs positively.
+	fn definitional_cnf(&mut self, expr: Expression) -> Result<Program> {
+		let mut clauses = Vec::new();
+		for conjunct in conjuncts(expr) {
+			let mut lits = Vec::new();
+			if self.disjuncts(conjunct, &mut lits, &mut clauses)? {
+				clauses.push(positives_first(lits));
+			}
+		}
+		Ok(Program::from(clauses))
+	}
+
+	/// Collects the literals of the disjunction `expr` into `lits`, naming its conjunctions. `⊥`
+	/// adds no literal. Returns false if the disjunction has a `⊤`, so it needs no clause.
+	fn disjuncts(
+		&mut self,
+		expr: Expression,
+		lits: &mut Vec<Literal>,
+		clauses: &mut Vec<Clause>,
+	) -> Result<bool> {
+		match expr {
+			Expression::Or(a, b) => {
+				let left = self.disjuncts(*a, lits, clauses)?;
+				Ok(self.disjuncts(*b, lits, clauses)? && left)
+			},
+			Expression::Bottom => Ok(true),
+			Expression::Not(inner) if *inner == Expression::Bottom => Ok(false),
+			conjunction @ Expression::And(..) => {
+				self.definitions += 1;
+				let mut vars = Vec::new();
+				expression_vars(&conjunction, &mut vars);
+				let definition = Proposition {
+					name: format!("{}{}", DEFINITION_PREFIX, self.definitions),
+					terms: vars.into_iter().map(Term::Identifier).collect(),
+				};
+				for conjunct in conjuncts(conjunction) {
+					let mut definition_lits = vec![Literal::Not(definition.clone())];
+					if self.disjuncts(conjunct, &mut definition_lits, clauses)? {
+						clauses.push(positives_first(definition_lits));
+					}
+				}
+				lits.push(Literal::Proposition(definition));
+				Ok(true)
+			},
+			leaf => {
+				lits.push(expr_to_literal(leaf)?);
+				Ok(true)
+			},
+		}
+	}
+
+	/// Declares that calls to `name/arity` are answered with tabling, see [`Program::table`].
+	pub fn table(&mut self, name: &str, arity: usize) {
+		self.program.table(name, arity);
+	}
+
 	pub fn get_program(&self) -> &Program {
 		&self.program
 	}
@@ -55,11 +181,93 @@ fn expr_to_literal(expr: Expression) -> Result<Literal> {
 		Expression::Proposition(p) => Ok(Literal::Proposition(p)),
 		Expression::Not(inner) => match *inner {
 			Expression::Proposition(p) => Ok(Literal::Not(p)),
+			// A negated call is a body goal, like a negative literal.
+			Expression::NegationAsFailure(goal) => {
+				let mut goals = Vec::new();
+				naf_goals(*goal, &mut goals)?;
+				Ok(Literal::NegationAsFailure(Clause(goals)))
+			},
 			_ => Err(GicError::ClauseError(format!("Not applied to non-proposition: {}", inner))),
 		},
+		Expression::NegationAsFailure(_) => Err(GicError::ClauseError(format!(
+			"Negation as failure can only be used in rule bodies and queries: {}",
+			expr
+		))),
 		_ => Err(GicError::ClauseError(format!("Expression is not a literal: {}", expr))),
 	}
 }
+
+/// Collects the goals of the conjunction `expr`, negated by negation as failure, into `goals`.
+/// The goal is not clausified, so it may only hold atoms and nested negations as failure.
+fn naf_goals(expr: Expression, goals: &mut Vec<Literal>) -> Result<()> {
+	match expr {
+		Expression::And(a, b) => {
+			naf_goals(*a, goals)?;
+			naf_goals(*b, goals)
+		},
+		Expression::Proposition(p) => {
+			goals.push(Literal::Not(p));
+			Ok(())
+		},
+		naf @ Expression::NegationAsFailure(_) => {
+			goals.push(expr_to_literal(Expression::Not(Box::new(naf)))?);
+			Ok(())
+		},
+		_ => Err(GicError::ClauseError(format!(
+			"Negation as failure only applies to conjunctions of atoms: {}",
+			expr
+		))),
+	}
+}
+
+/// The conjuncts of `expr`, which is a single one if it is not a conjunction.
+fn conjuncts(expr: Expression) -> Vec<Expression> {
+	match expr {
+		Expression::And(a, b) => {
+			let mut left = conjuncts(*a);
+			left.extend(conjuncts(*b));
+			left
+		},
+		other => vec![other],
+	}
+}
+
+/// Collects the variables of `expr` in order of first appearance.
+fn expression_vars(expr: &Expression, vars: &mut Vec<String>) {
+	fn term_vars(term: &Term, vars: &mut Vec<String>) {
+		match term {
+			Term::Identifier(var) if !vars.contains(var) => vars.push(var.clone()),
+			Term::FunctionApplication { args, .. } => args.iter().for_each(|a| term_vars(a, vars)),
+			_ => {},
+		}
+	}
+	match expr {
+		Expression::Proposition(p) => p.terms.iter().for_each(|t| term_vars(t, vars)),
+		Expression::And(a, b)
+		| Expression::Or(a, b)
+		| Expression::Xor(a, b)
+		| Expression::Implies(a, b)
+		| Expression::ImpliedBy(a, b)
+		| Expression::Iff(a, b) => {
+			expression_vars(a, vars);
+			expression_vars(b, vars);
+		},
+		Expression::Not(e)
+		| Expression::NegationAsFailure(e)
+		| Expression::Exists(_, e)
+		| Expression::ForAll(_, e) => expression_vars(e, vars),
+		Expression::Bottom | Expression::Top => {},
+	}
+}
+
+/// A clause of `lits` with the positive literals first, as clauses are stored.
+fn positives_first(lits: Vec<Literal>) -> Clause {
+	let (mut positives, mut negatives): (Vec<_>, Vec<_>) =
+		lits.into_iter().partition(|lit| lit.is_positive());
+	positives.append(&mut negatives);
+	Clause(positives)
+}
+
 pub fn flatten_cnf(expr: Expression) -> Result<Program> {
 	match expr {
 		Expression::And(a, b) => {
@@ -90,9 +298,13 @@ pub fn flatten_cnf(expr: Expression) -> Result<Program> {
 				}
 			}
 
-			Ok(Program(result))
+			Ok(Program::from(result))
 		},
 
+		// `⊥` is the empty clause, and `⊤` the empty conjunction of clauses.
+		Expression::Bottom => Ok(Program::from(vec![Clause(vec![])])),
+		Expression::Not(inner) if *inner == Expression::Bottom => Ok(Program::from(vec![])),
+
 		leaf => {
 			let lit = expr_to_literal(leaf)?;
 			// Put positive literal first (leaf is a single literal, so just wrap in Vec)
@@ -102,17 +314,42 @@ pub fn flatten_cnf(expr: Expression) -> Result<Program> {
 				// If single literal is negative, it goes after positives, so positives empty then negative
 				vec![lit]
 			};
-			Ok(Program(vec![Clause(clause_vec)]))
+			Ok(Program::from(vec![Clause(clause_vec)]))
 		},
 	}
 }
 
+/// Rewrites the connectives other than `∧`, `∨` and `¬` with these three, and `⊤` as `¬⊥`. The
+/// bi-implication and the exclusive or become conjunctions, which are already in CNF if their
+/// operands are literals.
 fn eliminate_implications(expr: Expression) -> Expression {
+	let not = |e: Expression| Box::new(Expression::Not(Box::new(e)));
 	match expr {
+		Expression::Top => Expression::Not(Box::new(Expression::Bottom)),
 		Expression::Implies(a, b) => Expression::Or(
 			Box::new(Expression::Not(Box::new(eliminate_implications(*a)))),
 			Box::new(eliminate_implications(*b)),
 		),
+		Expression::ImpliedBy(a, b) => Expression::Or(
+			Box::new(eliminate_implications(*a)),
+			Box::new(Expression::Not(Box::new(eliminate_implications(*b)))),
+		),
+		// A ⇔ B is (¬A ∨ B) ∧ (A ∨ ¬B).
+		Expression::Iff(a, b) => {
+			let (a, b) = (eliminate_implications(*a), eliminate_implications(*b));
+			Expression::And(
+				Box::new(Expression::Or(not(a.clone()), Box::new(b.clone()))),
+				Box::new(Expression::Or(Box::new(a), not(b))),
+			)
+		},
+		// A ⊕ B is (A ∨ B) ∧ (¬A ∨ ¬B).
+		Expression::Xor(a, b) => {
+			let (a, b) = (eliminate_implications(*a), eliminate_implications(*b));
+			Expression::And(
+				Box::new(Expression::Or(Box::new(a.clone()), Box::new(b.clone()))),
+				Box::new(Expression::Or(not(a), not(b))),
+			)
+		},
 		Expression::And(a, b) => Expression::And(
 			Box::new(eliminate_implications(*a)),
 			Box::new(eliminate_implications(*b)),
@@ -156,55 +393,222 @@ fn to_nnf(expr: Expression) -> Expression {
 	}
 }
 
-fn distribute_quantifiers(expr: Expression) -> Expression {
+/// Renames the bound variables of `expr` so that each quantifier binds a different variable, which
+/// is not also free in `expr`. Removing the universal quantifiers then cannot capture a variable:
+/// `(∀X: P(X)) ∨ Q(X)` must give the clause `{P(X1), Q(X)}`, not `{P(X), Q(X)}`. A quantifier
+/// keeps the name of its variable unless it is taken, and otherwise gets the first of `X1`, `X2`,
+/// ... that appears nowhere in `expr`.
+fn standardize_apart(expr: Expression) -> Expression {
+	let mut renamer = Renamer { taken: HashSet::new(), used: HashSet::new() };
+	renamer.collect(&expr, &mut vec![]);
+	renamer.rename(expr, &HashMap::new())
+}
+
+struct Renamer {
+	/// The free variables and the names given to quantifiers so far.
+	taken: HashSet<String>,
+	/// Every variable name of the formula, free or bound.
+	used: HashSet<String>,
+}
+
+impl Renamer {
+	/// Collects the free variables of `expr` into `taken`, and all its variables into `used`.
+	fn collect(&mut self, expr: &Expression, bound: &mut Vec<String>) {
+		fn term_vars(term: &Term, vars: &mut Vec<String>) {
+			match term {
+				Term::Identifier(var) => vars.push(var.clone()),
+				Term::FunctionApplication { args, .. } => {
+					args.iter().for_each(|arg| term_vars(arg, vars))
+				},
+				Term::Number(_) | Term::Var(_) => {},
+			}
+		}
+
+		match expr {
+			Expression::Proposition(p) => {
+				let mut vars = Vec::new();
+				p.terms.iter().for_each(|term| term_vars(term, &mut vars));
+				for var in vars {
+					if !bound.contains(&var) {
+						self.taken.insert(var.clone());
+					}
+					self.used.insert(var);
+				}
+			},
+			Expression::And(a, b)
+			| Expression::Or(a, b)
+			| Expression::Xor(a, b)
+			| Expression::Implies(a, b)
+			| Expression::ImpliedBy(a, b)
+			| Expression::Iff(a, b) => {
+				self.collect(a, bound);
+				self.collect(b, bound);
+			},
+			Expression::Not(e) | Expression::NegationAsFailure(e) => self.collect(e, bound),
+			Expression::Exists(var, e) | Expression::ForAll(var, e) => {
+				self.used.insert(var.clone());
+				bound.push(var.clone());
+				self.collect(e, bound);
+				bound.pop();
+			},
+			Expression::Bottom | Expression::Top => {},
+		}
+	}
+
+	/// A name for a quantifier over `var`, which is `var` itself if no other quantifier or free
+	/// variable took it.
+	fn fresh(&mut self, var: &str) -> String {
+		if self.taken.insert(var.to_string()) {
+			return var.to_string();
+		}
+		let name = (1..).map(|i| format!("{}{}", var, i)).find(|n| !self.used.contains(n)).unwrap();
+		self.taken.insert(name.clone());
+		self.used.insert(name.clone());
+		name
+	}
+
+	/// Renames the quantifiers of `expr`, and its variables as given by `renames`.
+	fn rename(&mut self, expr: Expression, renames: &HashMap<String, String>) -> Expression {
+		match expr {
+			Expression::Proposition(p) => Expression::Proposition(Proposition {
+				name: p.name,
+				terms: p.terms.into_iter().map(|t| rename_term(t, renames)).collect(),
+			}),
+			Expression::And(a, b) => {
+				let a = self.rename(*a, renames);
+				Expression::And(Box::new(a), Box::new(self.rename(*b, renames)))
+			},
+			Expression::Or(a, b) => {
+				let a = self.rename(*a, renames);
+				Expression::Or(Box::new(a), Box::new(self.rename(*b, renames)))
+			},
+			Expression::Xor(a, b) => {
+				let a = self.rename(*a, renames);
+				Expression::Xor(Box::new(a), Box::new(self.rename(*b, renames)))
+			},
+			Expression::Implies(a, b) => {
+				let a = self.rename(*a, renames);
+				Expression::Implies(Box::new(a), Box::new(self.rename(*b, renames)))
+			},
+			Expression::ImpliedBy(a, b) => {
+				let a = self.rename(*a, renames);
+				Expression::ImpliedBy(Box::new(a), Box::new(self.rename(*b, renames)))
+			},
+			Expression::Iff(a, b) => {
+				let a = self.rename(*a, renames);
+				Expression::Iff(Box::new(a), Box::new(self.rename(*b, renames)))
+			},
+			Expression::Not(e) => Expression::Not(Box::new(self.rename(*e, renames))),
+			Expression::NegationAsFailure(e) => {
+				Expression::NegationAsFailure(Box::new(self.rename(*e, renames)))
+			},
+			Expression::Exists(var, e) => {
+				let name = self.fresh(&var);
+				let renames = with_rename(renames, var, name.clone());
+				Expression::Exists(name, Box::new(self.rename(*e, &renames)))
+			},
+			Expression::ForAll(var, e) => {
+				let name = self.fresh(&var);
+				let renames = with_rename(renames, var, name.clone());
+				Expression::ForAll(name, Box::new(self.rename(*e, &renames)))
+			},
+			Expression::Bottom => Expression::Bottom,
+			Expression::Top => Expression::Top,
+		}
+	}
+}
+
+fn with_rename(
+	renames: &HashMap<String, String>,
+	var: String,
+	name: String,
+) -> HashMap<String, String> {
+	let mut renames = renames.clone();
+	renames.insert(var, name);
+	renames
+}
+
+fn rename_term(term: Term, renames: &HashMap<String, String>) -> Term {
+	match term {
+		Term::Identifier(var) => Term::Identifier(renames.get(&var).cloned().unwrap_or(var)),
+		Term::FunctionApplication { name, args } => Term::FunctionApplication {
+			name,
+			args: args.into_iter().map(|arg| rename_term(arg, renames)).collect(),
+		},
+		other => other,
+	}
+}
+
+/// Pushes the quantifiers of a formula in negation normal form as far inward as they go, so that
+/// Skolem functions only take the universals an existential depends on.
+///
+/// Distributing `∀X` over `∧` quantifies both conjuncts over `X`, so the result is no longer
+/// standardized apart. This is harmless: the two quantifiers only share a conjunction, and once
+/// they are removed `(∀X: A) ∧ (∀X: B)` reads as `∀X: (A ∧ B)`, which is equivalent. The other
+/// moves keep a single quantifier per variable.
+///
+/// A quantifier over a variable that does not occur is dropped, `∀` is distributed over `∧` and
+/// `∃` over `∨`, and a quantifier over a disjunction or conjunction moves to the side holding its
+/// variable when the other side does not.
+fn miniscope(expr: Expression) -> Expression {
 	match expr {
-		Expression::And(a, b) => match (*a, *b) {
-			(Expression::ForAll(x, a1), b1) => Expression::ForAll(
-				x,
-				Box::new(distribute_quantifiers(Expression::And(a1, Box::new(b1)))),
-			),
-			(a1, Expression::ForAll(x, b1)) => Expression::ForAll(
-				x,
-				Box::new(distribute_quantifiers(Expression::And(Box::new(a1), b1))),
-			),
-			(Expression::Exists(x, a1), b1) => Expression::Exists(
-				x,
-				Box::new(distribute_quantifiers(Expression::And(a1, Box::new(b1)))),
-			),
-			(a1, Expression::Exists(x, b1)) => Expression::Exists(
-				x,
-				Box::new(distribute_quantifiers(Expression::And(Box::new(a1), b1))),
-			),
-			(a2, b2) => Expression::And(
-				Box::new(distribute_quantifiers(a2)),
-				Box::new(distribute_quantifiers(b2)),
-			),
+		Expression::And(a, b) => Expression::And(Box::new(miniscope(*a)), Box::new(miniscope(*b))),
+		Expression::Or(a, b) => Expression::Or(Box::new(miniscope(*a)), Box::new(miniscope(*b))),
+		Expression::ForAll(var, e) => push_quantifier(Quantifier::ForAll, var, miniscope(*e)),
+		Expression::Exists(var, e) => push_quantifier(Quantifier::Exists, var, miniscope(*e)),
+		other => other,
+	}
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Quantifier {
+	ForAll,
+	Exists,
+}
+
+impl Quantifier {
+	fn wrap(self, var: String, body: Expression) -> Expression {
+		match self {
+			Quantifier::ForAll => Expression::ForAll(var, Box::new(body)),
+			Quantifier::Exists => Expression::Exists(var, Box::new(body)),
+		}
+	}
+}
+
+/// Quantifies `body`, which is already miniscoped, over `var`, as deep inside it as possible.
+fn push_quantifier(quantifier: Quantifier, var: String, body: Expression) -> Expression {
+	if !body.has_free_var(&var) {
+		return body;
+	}
+	match (quantifier, body) {
+		(Quantifier::ForAll, Expression::And(a, b)) => Expression::And(
+			Box::new(push_quantifier(quantifier, var.clone(), *a)),
+			Box::new(push_quantifier(quantifier, var, *b)),
+		),
+		(Quantifier::Exists, Expression::Or(a, b)) => Expression::Or(
+			Box::new(push_quantifier(quantifier, var.clone(), *a)),
+			Box::new(push_quantifier(quantifier, var, *b)),
+		),
+		(_, Expression::And(a, b)) if !a.has_free_var(&var) => {
+			Expression::And(a, Box::new(push_quantifier(quantifier, var, *b)))
 		},
-		Expression::Or(a, b) => match (*a, *b) {
-			(Expression::ForAll(x, a1), b1) => Expression::ForAll(
-				x,
-				Box::new(distribute_quantifiers(Expression::Or(a1, Box::new(b1)))),
-			),
-			(a1, Expression::ForAll(x, b1)) => Expression::ForAll(
-				x,
-				Box::new(distribute_quantifiers(Expression::Or(Box::new(a1), b1))),
-			),
-			(Expression::Exists(x, a1), b1) => Expression::Exists(
-				x,
-				Box::new(distribute_quantifiers(Expression::Or(a1, Box::new(b1)))),
-			),
-			(a1, Expression::Exists(x, b1)) => Expression::Exists(
-				x,
-				Box::new(distribute_quantifiers(Expression::Or(Box::new(a1), b1))),
-			),
-			(a2, b2) => Expression::Or(
-				Box::new(distribute_quantifiers(a2)),
-				Box::new(distribute_quantifiers(b2)),
-			),
+		(_, Expression::And(a, b)) if !b.has_free_var(&var) => {
+			Expression::And(Box::new(push_quantifier(quantifier, var, *a)), b)
 		},
-		Expression::Exists(x, e) => Expression::Exists(x, Box::new(distribute_quantifiers(*e))),
-		Expression::ForAll(x, e) => Expression::ForAll(x, Box::new(distribute_quantifiers(*e))),
-		other => other,
+		(_, Expression::Or(a, b)) if !a.has_free_var(&var) => {
+			Expression::Or(a, Box::new(push_quantifier(quantifier, var, *b)))
+		},
+		(_, Expression::Or(a, b)) if !b.has_free_var(&var) => {
+			Expression::Or(Box::new(push_quantifier(quantifier, var, *a)), b)
+		},
+		// Quantifiers of the same kind commute, so `var` can go under the inner one.
+		(Quantifier::ForAll, Expression::ForAll(inner, e)) => {
+			Expression::ForAll(inner, Box::new(push_quantifier(quantifier, var, *e)))
+		},
+		(Quantifier::Exists, Expression::Exists(inner, e)) => {
+			Expression::Exists(inner, Box::new(push_quantifier(quantifier, var, *e)))
+		},
+		(_, body) => quantifier.wrap(var, body),
 	}
 }
 
@@ -250,15 +654,16 @@ mod tests {
 
 		let clause = clausifier.clausify(expr).unwrap();
 
-		let expected_program = Program(vec![Clause(vec![
-			Literal::Not(Proposition {
-				name: "P".to_string(),
-				terms: vec![Term::Identifier("X".to_string())],
-			}),
+		// Positive literals come first, so the head of the rule leads the clause.
+		let expected_program = Program::from(vec![Clause(vec![
 			Literal::Proposition(Proposition {
 				name: "Q".to_string(),
 				terms: vec![Term::Identifier("X".to_string())],
 			}),
+			Literal::Not(Proposition {
+				name: "P".to_string(),
+				terms: vec![Term::Identifier("X".to_string())],
+			}),
 		])]);
 		assert_eq!(clause, expected_program);
 	}
@@ -278,7 +683,7 @@ mod tests {
 		);
 
 		let program = clausifier.clausify(expr).unwrap();
-		let expected_program = Program(vec![
+		let expected_program = Program::from(vec![
 			Clause(vec![Literal::Proposition(Proposition {
 				name: "P".to_string(),
 				terms: vec![Term::Identifier("X".to_string())],
@@ -310,17 +715,173 @@ mod tests {
 
 		let program = clausifier.clausify(expr).unwrap();
 
-		let expected_program = Program(vec![Clause(vec![Literal::Proposition(Proposition {
-			name: "R".to_string(),
-			terms: vec![
-				Term::Identifier("X".to_string()),
-				Term::FunctionApplication {
-					name: "_Y_1".to_string(),
-					args: vec![Term::Identifier("X".to_string())],
-				},
-			],
-		})])]);
+		let expected_program =
+			Program::from(vec![Clause(vec![Literal::Proposition(Proposition {
+				name: "R".to_string(),
+				terms: vec![
+					Term::Identifier("X".to_string()),
+					Term::FunctionApplication {
+						name: "_Y_1".to_string(),
+						args: vec![Term::Identifier("X".to_string())],
+					},
+				],
+			})])]);
+
+		assert_eq!(program, expected_program);
+	}
+
+	fn parse(formula: &str) -> Expression {
+		crate::parser::parse_formula(&format!("{};", formula)).unwrap()
+	}
+
+	fn constant(name: &str) -> Term {
+		Term::FunctionApplication { name: name.to_string(), args: vec![] }
+	}
+
+	#[test]
+	fn test_skolem_constant_is_not_a_variable() {
+		let mut clausifier = Clausifier::new();
+		let expr = Expression::Exists(
+			"X".to_string(),
+			Box::new(Expression::Proposition(Proposition {
+				name: "P".to_string(),
+				terms: vec![Term::Identifier("X".to_string())],
+			})),
+		);
+
+		let program = clausifier.clausify(expr).unwrap();
 
+		let expected_program =
+			Program::from(vec![Clause(vec![Literal::Proposition(Proposition {
+				name: "P".to_string(),
+				terms: vec![constant("_X_1")],
+			})])]);
 		assert_eq!(program, expected_program);
+		assert!(program.clauses()[0].fv().is_empty());
+	}
+
+	#[test]
+	fn test_skolem_symbols_are_fresh() {
+		let exists_x = |name: &str| {
+			Expression::Exists(
+				"X".to_string(),
+				Box::new(Expression::Proposition(Proposition {
+					name: name.to_string(),
+					terms: vec![Term::Identifier("X".to_string())],
+				})),
+			)
+		};
+		let mut ctx = SkolemContext::new();
+		let expr = Expression::And(Box::new(exists_x("P")), Box::new(exists_x("Q")));
+
+		let skolemized = ctx.deskolem(expr);
+
+		let expected = Expression::And(
+			Box::new(Expression::Proposition(Proposition {
+				name: "P".to_string(),
+				terms: vec![constant("_X_1")],
+			})),
+			Box::new(Expression::Proposition(Proposition {
+				name: "Q".to_string(),
+				terms: vec![constant("_X_2")],
+			})),
+		);
+		assert_eq!(skolemized, expected);
+		// Later formulas get fresh symbols too.
+		assert_eq!(ctx.deskolem(exists_x("R")).to_string(), "R(_X_3())");
+	}
+
+	#[test]
+	fn test_standardize_apart() {
+		let captured = standardize_apart(parse("(forall X. P(X)) and Q(X)"));
+		assert_eq!(captured.to_string(), "(∀X1: P(X1) ∧ Q(X))");
+		let nested = standardize_apart(parse("forall X. (P(X) or (exists X. Q(X, X1)))"));
+		assert_eq!(nested.to_string(), "∀X: (P(X) ∨ ∃X2: Q(X2, X1))");
+
+		let mut clausifier = Clausifier::new();
+		let program = clausifier.clausify(parse("(forall X. T(X)) or (forall X. U(X))")).unwrap();
+		assert_eq!(program.clauses()[0].to_string(), "{T(X), U(X1)}");
+		let program = clausifier.clausify(parse("(forall X. P(X)) or Q(X)")).unwrap();
+		assert_eq!(program.clauses()[0].to_string(), "{P(X1), Q(X)}");
+		// Miniscoping quantifies both conjuncts over `X`, which end up in different clauses.
+		let program = clausifier.clausify(parse("forall X. ((P(X) and Q(X)) or R)")).unwrap();
+		let clauses: Vec<String> = program.clauses().iter().map(|c| c.to_string()).collect();
+		assert_eq!(clauses, vec!["{P(X), R}", "{Q(X), R}"]);
+	}
+
+	#[test]
+	fn test_definitional_cnf() {
+		let formula = "(P(X) and Q(X)) or (R and (S(Y) or T(Y)))";
+
+		let distributive = Clausifier::new().clausify(parse(formula)).unwrap();
+		assert_eq!(distributive.clauses().len(), 4);
+
+		let mut clausifier = Clausifier::new().with_mode(CnfMode::Definitional);
+		let program = clausifier.clausify(parse(formula)).unwrap();
+		let clauses: Vec<String> = program.clauses().iter().map(|c| c.to_string()).collect();
+		assert_eq!(
+			clauses,
+			vec![
+				"{P(X), ¬_def1(X)}",
+				"{Q(X), ¬_def1(X)}",
+				"{R, ¬_def2(Y)}",
+				"{S(Y), T(Y), ¬_def2(Y)}",
+				"{_def1(X), _def2(Y)}",
+			]
+		);
+
+		// A disjunction of n conjunctions gives 2^n clauses when distributed, but 2n + 1 here.
+		let big = (1..=10).map(|i| format!("(P{}(X) and Q{}(X))", i, i)).collect::<Vec<_>>();
+		let big = parse(&big.join(" or "));
+		assert_eq!(Clausifier::new().clausify(big.clone()).unwrap().clauses().len(), 1024);
+		assert_eq!(clausifier.clausify(big).unwrap().clauses().len(), 21);
+	}
+
+	#[test]
+	fn test_miniscoping() {
+		let miniscoped = miniscope(parse("forall X. exists Y. (P(X) and Q(Y))"));
+		assert_eq!(miniscoped.to_string(), "(∀X: P(X) ∧ ∃Y: Q(Y))");
+		let miniscoped = miniscope(parse("exists Y. forall X. (P(X) or Q(Y))"));
+		assert_eq!(miniscoped.to_string(), "(∀X: P(X) ∨ ∃Y: Q(Y))");
+		let miniscoped = miniscope(parse("forall X. forall Y. (R(X, Y) or S(X))"));
+		assert_eq!(miniscoped.to_string(), "∀X: (∀Y: R(X, Y) ∨ S(X))");
+		let miniscoped = miniscope(parse("forall X. ((P(X) and Q(X)) or R)"));
+		assert_eq!(miniscoped.to_string(), "((∀X: P(X) ∧ ∀X: Q(X)) ∨ R)");
+
+		// Skolem terms only take the universals the existential depends on.
+		let mut clausifier = Clausifier::new();
+		let program = clausifier
+			.clausify(parse("forall X. forall Y. exists Z. (R(Y, Z) or S(X))"))
+			.unwrap();
+		assert_eq!(program.clauses()[0].to_string(), "{R(Y, _Z_1(Y)), S(X)}");
+		let program = clausifier.clausify(parse("forall X. exists Y. (P(X) and Q(Y))")).unwrap();
+		let clauses: Vec<String> = program.clauses().iter().map(|c| c.to_string()).collect();
+		assert_eq!(clauses, vec!["{P(X)}", "{Q(_Y_2())}"]);
+	}
+
+	#[test]
+	fn test_iff_xor_and_constants() {
+		let clauses = |formula: &str, mode: CnfMode| -> Vec<String> {
+			let program = Clausifier::new().with_mode(mode).clausify(parse(formula)).unwrap();
+			program.clauses().iter().map(|c| c.to_string()).collect()
+		};
+		for mode in [CnfMode::Distributive, CnfMode::Definitional] {
+			assert_eq!(clauses("P(X) iff Q(X)", mode), vec!["{Q(X), ¬P(X)}", "{P(X), ¬Q(X)}"]);
+			assert_eq!(clauses("P xor Q", mode), vec!["{P, Q}", "{¬P, ¬Q}"]);
+			assert_eq!(clauses("P if Q and R", mode), vec!["{P, ¬Q, ¬R}"]);
+			assert_eq!(clauses("P or top", mode), Vec::<String>::new());
+			assert_eq!(clauses("(P or bottom) and (Q or not top)", mode), vec!["{P}", "{Q}"]);
+			assert_eq!(clauses("bottom", mode), vec!["{}"]);
+		}
+	}
+
+	#[test]
+	fn test_negation_as_failure_outside_goals() {
+		let mut clausifier = Clausifier::new();
+
+		let err = clausifier.clausify(parse("\\+ Penguin(tweety)")).unwrap_err();
+		assert!(matches!(err, GicError::ClauseError(_)));
+		let err = clausifier.clausify(parse("\\+ (Bird(X) or Penguin(X)) => Odd(X)")).unwrap_err();
+		assert!(matches!(err, GicError::ClauseError(_)));
 	}
 }
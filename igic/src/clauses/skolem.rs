@@ -1,23 +1,24 @@
 use crate::types::ast::Expression;
 use crate::types::ast::Term;
 
+/// Names the Skolem symbols of a program.
 #[derive(Debug, Clone)]
 pub struct SkolemContext {
-	clause_id: usize,
+	/// The number of Skolem symbols created so far.
+	count: usize,
 }
 
 impl SkolemContext {
 	pub fn new() -> Self {
-		Self { clause_id: 1 }
-	}
-
-	pub fn set_clause_id(&mut self, id: usize) {
-		self.clause_id = id;
+		Self { count: 0 }
 	}
 
+	/// A fresh Skolem symbol for the existential variable `var`, like `_Y_3`. Each call returns a
+	/// different name, and since constants and functions written by the user start with a lowercase
+	/// letter, it never clashes with one of theirs.
 	pub fn next_name(&mut self, var: &str) -> String {
-		let name = format!("_{}_{}", var, self.clause_id);
-		name
+		self.count += 1;
+		format!("_{}_{}", var, self.count)
 	}
 
 	pub fn deskolem(&mut self, expr: Expression) -> Expression {
@@ -30,20 +31,17 @@ fn deskolem(expr: Expression, scope: &mut Vec<String>, ctx: &mut SkolemContext)
 		Expression::Exists(var, inner) => {
 			let var = var.trim_end_matches('.').to_string();
 
+			// The witness only depends on the universals it mentions.
 			let mut seen = std::collections::HashSet::new();
 			let mut args: Vec<Term> = vec![];
 			for v in scope.iter() {
-				if seen.insert(v) {
+				if inner.has_free_var(v) && seen.insert(v) {
 					args.push(Term::Identifier(v.clone()));
 				}
 			}
 
-			let name = ctx.next_name(&var);
-			let skolem_term: Term = if scope.len() == 0 {
-				Term::Identifier(name.clone())
-			} else {
-				Term::FunctionApplication { name, args }
-			};
+			// Without universals in scope, `args` is empty and the Skolem term is a constant.
+			let skolem_term = Term::FunctionApplication { name: ctx.next_name(&var), args };
 
 			let substituted = substitute_var(*inner, &var, &skolem_term);
 			deskolem(substituted, scope, ctx)
@@ -80,6 +78,9 @@ fn substitute_var(expr: Expression, var: &str, replacement: &Term) -> Expression
 		Expression::Not(inner) => {
 			Expression::Not(Box::new(substitute_var(*inner, var, replacement)))
 		},
+		Expression::NegationAsFailure(inner) => {
+			Expression::NegationAsFailure(Box::new(substitute_var(*inner, var, replacement)))
+		},
 		Expression::And(a, b) => Expression::And(
 			Box::new(substitute_var(*a, var, replacement)),
 			Box::new(substitute_var(*b, var, replacement)),
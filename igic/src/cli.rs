@@ -0,0 +1,357 @@
+use gic::json::{JsonValue, ToJson};
+use gic::{Answer, Engine, GicError, LimitExceeded, Limits, OccursCheck};
+use std::time::Duration;
+
+pub const USAGE: &str = "Usage:\n\
+	igic                                   Start the interactive REPL.\n\
+	igic run <file>... --query \"<expr>\"... [--all] [--limit N] [--format text|json]\n\
+	\n\
+	Options for run:\n\
+	-q, --query \"<expr>\"  Query to run against the loaded files. May be repeated.\n\
+	-a, --all             Print every solution instead of only the first one.\n\
+	-n, --limit N         Stop after N solutions.\n\
+	-f, --format FORMAT   Output format: text (default) or json, one object per line.\n\
+	--max-depth N         Prune derivations deeper than N resolution steps.\n\
+	--max-steps N         Stop a query after N inference steps.\n\
+	--timeout SECONDS     Stop a query after SECONDS of wall-clock time.\n\
+	--occurs-check MODE   Occurs check in unification: off (default), on, or error.\n\
+	\n\
+	Exit codes: 0 if every query succeeds, 1 if a query has no solution,\n\
+	2 on usage, parse, clausify or occurs-check errors, 3 if a query found\n\
+	no solution before exceeding a resource limit. With several queries, an\n\
+	error takes priority over a resource limit, which takes priority over a\n\
+	query without solution.";
+
+pub const EXIT_SUCCESS: i32 = 0;
+pub const EXIT_FAILURE: i32 = 1;
+pub const EXIT_ERROR: i32 = 2;
+pub const EXIT_LIMIT: i32 = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum OutputFormat {
+	Text,
+	Json,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct RunOptions {
+	pub files: Vec<String>,
+	pub queries: Vec<String>,
+	pub all: bool,
+	pub limit: Option<usize>,
+	pub format: OutputFormat,
+	pub limits: Limits,
+	pub occurs_check: OccursCheck,
+}
+
+impl RunOptions {
+	/// Number of solutions to print per query, `None` meaning all of them.
+	fn max_answers(&self) -> Option<usize> {
+		match (self.limit, self.all) {
+			(Some(n), _) => Some(n),
+			(None, true) => None,
+			(None, false) => Some(1),
+		}
+	}
+}
+
+pub fn parse_args(args: &[String]) -> Result<RunOptions, String> {
+	let mut options = RunOptions {
+		files: vec![],
+		queries: vec![],
+		all: false,
+		limit: None,
+		format: OutputFormat::Text,
+		limits: Limits::unbounded(),
+		occurs_check: OccursCheck::Off,
+	};
+
+	let mut args = args.iter();
+	while let Some(arg) = args.next() {
+		match arg.as_str() {
+			"-q" | "--query" => {
+				let query = args.next().ok_or_else(|| format!("Missing value for {}", arg))?;
+				options.queries.push(query.clone());
+			},
+			"-a" | "--all" => options.all = true,
+			"-n" | "--limit" => {
+				let limit = parse_value(arg, args.next())?;
+				if limit == 0 {
+					return Err(format!("The value for {} must be at least 1.", arg));
+				}
+				options.limit = Some(limit);
+			},
+			"-f" | "--format" => {
+				let value = args.next().ok_or_else(|| format!("Missing value for {}", arg))?;
+				options.format = match value.as_str() {
+					"text" => OutputFormat::Text,
+					"json" => OutputFormat::Json,
+					_ => return Err(format!("Invalid value for {}: '{}'", arg, value)),
+				};
+			},
+			"--max-depth" => options.limits.max_depth = Some(parse_value(arg, args.next())?),
+			"--max-steps" => options.limits.max_steps = Some(parse_value(arg, args.next())?),
+			"--timeout" => {
+				let secs: f64 = parse_value(arg, args.next())?;
+				let timeout = Duration::try_from_secs_f64(secs)
+					.map_err(|_| format!("Invalid value for {}: '{}'", arg, secs))?;
+				options.limits.timeout = Some(timeout);
+			},
+			"--occurs-check" => options.occurs_check = parse_value(arg, args.next())?,
+			flag if flag.starts_with('-') => return Err(format!("Unknown option: '{}'", flag)),
+			file => options.files.push(file.to_string()),
+		}
+	}
+
+	if options.queries.is_empty() {
+		return Err("At least one --query is required.".to_string());
+	}
+	Ok(options)
+}
+
+fn parse_value<T: std::str::FromStr>(arg: &str, value: Option<&String>) -> Result<T, String> {
+	let value = value.ok_or_else(|| format!("Missing value for {}", arg))?;
+	value.parse().map_err(|_| format!("Invalid value for {}: '{}'", arg, value))
+}
+
+/// Runs `igic run` with the arguments following the subcommand, returning the exit code.
+pub fn run(args: &[String]) -> i32 {
+	let options = match parse_args(args) {
+		Ok(options) => options,
+		Err(msg) => {
+			eprintln!("Error: {}\n\n{}", msg, USAGE);
+			return EXIT_ERROR;
+		},
+	};
+
+	let mut engine = Engine::new();
+	engine.set_limits(options.limits);
+	engine.set_occurs_check(options.occurs_check);
+	for file in &options.files {
+		if let Err(e) = engine.load_file(file) {
+			report_error(options.format, ("file", file), &e);
+			return EXIT_ERROR;
+		}
+	}
+
+	let mut exit_code = EXIT_SUCCESS;
+	for query in &options.queries {
+		let code = run_query(&mut engine, query, &options);
+		exit_code = worst_exit_code(exit_code, code);
+	}
+	exit_code
+}
+
+/// The exit code reporting the worse of two outcomes: an error, then a resource limit, then a
+/// query without solution.
+fn worst_exit_code(a: i32, b: i32) -> i32 {
+	let severity = |code| match code {
+		EXIT_ERROR => 3,
+		EXIT_LIMIT => 2,
+		EXIT_FAILURE => 1,
+		_ => 0,
+	};
+	if severity(b) > severity(a) {
+		b
+	} else {
+		a
+	}
+}
+
+fn run_query(engine: &mut Engine, query: &str, options: &RunOptions) -> i32 {
+	let solver = match engine.query(query) {
+		Ok(solver) => solver,
+		Err(e) => {
+			report_error(options.format, ("query", query), &e);
+			return EXIT_ERROR;
+		},
+	};
+
+	let mut solver = solver;
+	let max_answers = options.max_answers().unwrap_or(usize::MAX);
+	let mut found = 0;
+	while found < max_answers {
+		match solver.next() {
+			Some(answer) => {
+				report_answer(options.format, query, &answer);
+				found += 1;
+			},
+			None => break,
+		}
+	}
+	// Warnings go to stderr in both formats, so that stdout only holds results.
+	for warning in solver.warnings() {
+		eprintln!("Warning: {}", warning);
+	}
+
+	if let Some(e) = solver.error() {
+		report_error(options.format, ("query", query), e);
+		return EXIT_ERROR;
+	}
+
+	if found < max_answers {
+		if let Some(limit) = solver.limit_exceeded() {
+			report_limit(options.format, query, limit);
+			return if found == 0 { EXIT_LIMIT } else { EXIT_SUCCESS };
+		}
+	}
+
+	if found == 0 {
+		report_false(options.format, query);
+		EXIT_FAILURE
+	} else {
+		EXIT_SUCCESS
+	}
+}
+
+fn report_answer(format: OutputFormat, query: &str, answer: &Answer) {
+	match format {
+		OutputFormat::Text => println!("{}", answer),
+		OutputFormat::Json => println!(
+			"{}",
+			JsonValue::object(vec![
+				("query", query.into()),
+				("result", "answer".into()),
+				("bindings", answer.to_json()),
+			])
+		),
+	}
+}
+
+fn report_false(format: OutputFormat, query: &str) {
+	match format {
+		OutputFormat::Text => println!("false."),
+		OutputFormat::Json => println!(
+			"{}",
+			JsonValue::object(vec![("query", query.into()), ("result", "false".into())])
+		),
+	}
+}
+
+fn report_limit(format: OutputFormat, query: &str, limit: LimitExceeded) {
+	match format {
+		OutputFormat::Text => println!("Resource limit exceeded: {}.", limit),
+		OutputFormat::Json => println!(
+			"{}",
+			JsonValue::object(vec![
+				("query", query.into()),
+				("result", "limit_exceeded".into()),
+				("limit", limit.kind().into()),
+				("message", limit.to_string().as_str().into()),
+			])
+		),
+	}
+}
+
+/// Reports an error about `source`, a `(kind, name)` pair such as `("file", "family.gic")`.
+/// JSON errors are written to stdout so they share a stream with the answers.
+fn report_error(format: OutputFormat, source: (&str, &str), e: &GicError) {
+	let (kind, name) = source;
+	match format {
+		OutputFormat::Text => eprintln!("Error in {} '{}': {}", kind, name, e),
+		OutputFormat::Json => println!(
+			"{}",
+			JsonValue::object(vec![
+				(kind, name.into()),
+				("result", "error".into()),
+				("error", e.to_json()),
+			])
+		),
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn args(args: &[&str]) -> Vec<String> {
+		args.iter().map(|a| a.to_string()).collect()
+	}
+
+	#[test]
+	fn test_parse_args() {
+		let options =
+			parse_args(&args(&["a.gic", "-q", "P(X)", "b.gic", "--query", "Q(X)", "--all"]))
+				.unwrap();
+		assert_eq!(
+			options,
+			RunOptions {
+				files: vec!["a.gic".to_string(), "b.gic".to_string()],
+				queries: vec!["P(X)".to_string(), "Q(X)".to_string()],
+				all: true,
+				limit: None,
+				format: OutputFormat::Text,
+				limits: Limits::unbounded(),
+				occurs_check: OccursCheck::Off,
+			}
+		);
+		assert_eq!(options.max_answers(), None);
+	}
+
+	#[test]
+	fn test_parse_args_limit() {
+		let options = parse_args(&args(&["--limit", "3", "-q", "P(X)"])).unwrap();
+		assert_eq!(options.max_answers(), Some(3));
+
+		let options = parse_args(&args(&["-q", "P(X)"])).unwrap();
+		assert_eq!(options.max_answers(), Some(1));
+	}
+
+	#[test]
+	fn test_parse_args_format() {
+		let options = parse_args(&args(&["-q", "P(X)", "--format", "json"])).unwrap();
+		assert_eq!(options.format, OutputFormat::Json);
+		assert!(parse_args(&args(&["-q", "P(X)", "-f", "xml"])).is_err());
+	}
+
+	#[test]
+	fn test_parse_args_resource_limits() {
+		let options = parse_args(&args(&[
+			"-q",
+			"P(X)",
+			"--max-depth",
+			"10",
+			"--max-steps",
+			"500",
+			"--timeout",
+			"1.5",
+		]))
+		.unwrap();
+		assert_eq!(
+			options.limits,
+			Limits {
+				max_depth: Some(10),
+				max_steps: Some(500),
+				timeout: Some(Duration::from_millis(1500)),
+			}
+		);
+		assert!(parse_args(&args(&["-q", "P(X)", "--timeout", "-1"])).is_err());
+	}
+
+	#[test]
+	fn test_parse_args_occurs_check() {
+		let options = parse_args(&args(&["-q", "P(X)", "--occurs-check", "error"])).unwrap();
+		assert_eq!(options.occurs_check, OccursCheck::Error);
+		assert!(parse_args(&args(&["-q", "P(X)", "--occurs-check", "maybe"])).is_err());
+	}
+
+	#[test]
+	fn test_worst_exit_code() {
+		assert_eq!(worst_exit_code(EXIT_LIMIT, EXIT_ERROR), EXIT_ERROR);
+		assert_eq!(worst_exit_code(EXIT_ERROR, EXIT_LIMIT), EXIT_ERROR);
+		assert_eq!(worst_exit_code(EXIT_FAILURE, EXIT_LIMIT), EXIT_LIMIT);
+		assert_eq!(worst_exit_code(EXIT_SUCCESS, EXIT_FAILURE), EXIT_FAILURE);
+	}
+
+	#[test]
+	fn test_parse_args_errors() {
+		assert!(parse_args(&args(&["a.gic"])).is_err());
+		assert!(parse_args(&args(&["-q"])).is_err());
+		assert!(parse_args(&args(&["-q", "P(X)", "--limit", "x"])).is_err());
+		assert_eq!(
+			parse_args(&args(&["-q", "P(X)", "-n", "0"])),
+			Err("The value for -n must be at least 1.".to_string())
+		);
+		assert!(parse_args(&args(&["-q", "P(X)", "--verbose"])).is_err());
+	}
+}
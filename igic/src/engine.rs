@@ -0,0 +1,399 @@
+use std::fs;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use crate::clauses::cnf::{Clausifier, CnfMode};
+use crate::libraries::{standard_builtins, BuiltinRegistry};
+use crate::mgu::mgu::OccursCheck;
+use crate::models::finder::{ModelFinder, ModelResult};
+use crate::parser::{parse_formula, parse_gic_program};
+use crate::resolution::limits::Limits;
+use crate::resolution::resolution::{sld_resolution, Solver};
+use crate::resolution::saturation::{ProofResult, Prover};
+use crate::types::ast::{Expression, Statement};
+use crate::types::clause::{Clause, Program};
+use crate::types::{GicError, Result};
+
+pub(crate) const LIBRARIES: &[(&str, &str)] = &[
+	("common.gic", include_str!("libraries/common.gic")),
+	("lists.gic", include_str!("libraries/lists/lists.gic")),
+];
+
+/// A GIC program together with the standard libraries, ready to be queried.
+pub struct Engine {
+	clausifier: Clausifier,
+	builtins: BuiltinRegistry,
+	limits: Limits,
+	occurs_check: OccursCheck,
+	interrupt: Option<Arc<AtomicBool>>,
+	library_length: usize,
+	/// The formulas loaded after the standard libraries, clausified again for proofs and model
+	/// searches when they use another [`CnfMode`].
+	formulas: Vec<Expression>,
+	cnf_mode: CnfMode,
+}
+
+impl Engine {
+	/// Creates an engine with the standard libraries already loaded.
+	pub fn new() -> Self {
+		let mut engine = Engine::empty();
+		for (name, content) in LIBRARIES {
+			if let Err(e) = engine.load_str(content) {
+				panic!("Error loading library {}: {}", name, e);
+			}
+		}
+		engine.library_length = engine.clausifier.get_progam_length();
+		engine.formulas.clear();
+		engine
+	}
+
+	/// Creates an engine without any library loaded. The builtin predicates are still available.
+	pub fn empty() -> Self {
+		Engine {
+			clausifier: Clausifier::new(),
+			builtins: standard_builtins(),
+			limits: Limits::unbounded(),
+			occurs_check: OccursCheck::default(),
+			interrupt: None,
+			library_length: 0,
+			formulas: Vec::new(),
+			cnf_mode: CnfMode::default(),
+		}
+	}
+
+	/// Parses the formulas in `source` and adds their clauses to the program, along with its
+	/// `:- table` declarations. Every formula is clausified before any is added, so on an error,
+	/// or when the interrupt flag is set between two formulas, the program is left as it was.
+	pub fn load_str(&mut self, source: &str) -> Result<()> {
+		let statements = parse_gic_program(source)?;
+		let mut clauses = Vec::new();
+		for statement in &statements {
+			if let Statement::Formula(expr) = statement {
+				if self.interrupt.as_ref().is_some_and(|flag| flag.load(Ordering::Relaxed)) {
+					return Err(GicError::Interrupted);
+				}
+				clauses.push(self.clausifier.clausify(expr.clone())?);
+			}
+		}
+
+		let mut clauses = clauses.into_iter();
+		for statement in statements {
+			match statement {
+				Statement::Formula(expr) => {
+					self.clausifier.add_clauses(clauses.next().unwrap());
+					self.formulas.push(expr);
+				},
+				Statement::Table(preds) => {
+					for (name, arity) in preds {
+						self.clausifier.table(&name, arity);
+					}
+				},
+			}
+		}
+		Ok(())
+	}
+
+	/// Reads a .gic file and adds its clauses to the program.
+	pub fn load_file<P: AsRef<Path>>(&mut self, path: P) -> Result<()> {
+		let content = fs::read_to_string(path)?;
+		self.load_str(&content)
+	}
+
+	/// The builtin predicates available to queries. Host applications can register their own
+	/// Rust-implemented predicates here.
+	pub fn builtins_mut(&mut self) -> &mut BuiltinRegistry {
+		&mut self.builtins
+	}
+
+	/// The resource limits applied to every query.
+	pub fn limits(&self) -> Limits {
+		self.limits
+	}
+
+	pub fn set_limits(&mut self, limits: Limits) {
+		self.limits = limits;
+	}
+
+	/// How unification treats cyclic bindings such as `X = f(X)`. Off by default, as in Prolog.
+	pub fn occurs_check(&self) -> OccursCheck {
+		self.occurs_check
+	}
+
+	pub fn set_occurs_check(&mut self, occurs_check: OccursCheck) {
+		self.occurs_check = occurs_check;
+	}
+
+	/// How `prove` and `model` turn the program into clauses. Queries always use the distributive
+	/// CNF, which keeps Horn programs Horn.
+	pub fn cnf_mode(&self) -> CnfMode {
+		self.cnf_mode
+	}
+
+	pub fn set_cnf_mode(&mut self, cnf_mode: CnfMode) {
+		self.cnf_mode = cnf_mode;
+	}
+
+	/// Makes every query, proof, model search and load stop as soon as `flag` is set. The caller
+	/// is responsible for clearing the flag before starting a new command.
+	pub fn set_interrupt(&mut self, flag: Arc<AtomicBool>) {
+		self.interrupt = Some(flag);
+	}
+
+	pub fn program(&self) -> &Program {
+		self.clausifier.get_program()
+	}
+
+	/// Returns true if clauses other than the standard libraries have been loaded.
+	pub fn has_user_program(&self) -> bool {
+		self.clausifier.get_progam_length() > self.library_length
+	}
+
+	/// Formats the clauses loaded by the user, skipping the standard libraries.
+	pub fn user_program_str(&self) -> String {
+		self.clausifier.to_str_from(self.library_length)
+	}
+
+	/// Parses `query` and turns its negation into the goal clause used by SLD resolution.
+	pub fn goal(&mut self, query: &str) -> Result<Clause> {
+		let goal_program = self.negate(query)?;
+		goal_program.get_clause(0).cloned().ok_or_else(|| {
+			GicError::ClauseError("No clauses found in the goal program.".to_string())
+		})
+	}
+
+	/// Parses `query` and clausifies its negation.
+	fn negate(&mut self, query: &str) -> Result<Program> {
+		let expr = parse_query(query)?;
+		self.clausifier.clausify(Expression::Not(Box::new(expr)))
+	}
+
+	/// The clauses of the user program, without the standard libraries, and of the negation of
+	/// `formula` if given, in the current [`CnfMode`].
+	fn proof_clauses(&mut self, formula: Option<&str>) -> Result<Vec<Clause>> {
+		let mut definitional;
+		let clausifier = match self.cnf_mode {
+			CnfMode::Distributive => &mut self.clausifier,
+			CnfMode::Definitional => {
+				definitional = Clausifier::new().with_mode(CnfMode::Definitional);
+				for expr in &self.formulas {
+					definitional.add_to_program(expr.clone())?;
+				}
+				&mut definitional
+			},
+		};
+		let negated_goal = match formula {
+			Some(formula) => {
+				clausifier.clausify(Expression::Not(Box::new(parse_query(formula)?)))?
+			},
+			None => Program::new(),
+		};
+		let program = match self.cnf_mode {
+			CnfMode::Distributive => &clausifier.get_program().clauses()[self.library_length..],
+			CnfMode::Definitional => clausifier.get_program().clauses(),
+		};
+		Ok(program.iter().chain(&negated_goal).cloned().collect())
+	}
+
+	/// Tries to prove that `formula` follows from the loaded program, Horn or not, by refuting its
+	/// negation with the saturation [`Prover`]. The standard libraries are left out of the proof.
+	pub fn prove(&mut self, formula: &str) -> Result<ProofResult> {
+		let clauses = self.proof_clauses(Some(formula))?;
+		let prover = Prover::new(&clauses)?.with_limits(self.limits);
+		let mut prover = match &self.interrupt {
+			Some(flag) => prover.with_interrupt(flag.clone()),
+			None => prover,
+		};
+		Ok(prover.run())
+	}
+
+	/// Searches for a finite model of the loaded program with at most `max_size` elements, using
+	/// the [`ModelFinder`]. With a `formula`, the model also satisfies its negation, so it is a
+	/// counter-example showing that the formula does not follow from the program. The standard
+	/// libraries are left out of the search.
+	pub fn model(&mut self, formula: Option<&str>, max_size: usize) -> Result<ModelResult> {
+		let clauses = self.proof_clauses(formula)?;
+		let finder = ModelFinder::new(&clauses)?.with_limits(self.limits);
+		let finder = match &self.interrupt {
+			Some(flag) => finder.with_interrupt(flag.clone()),
+			None => finder,
+		};
+		Ok(finder.find(max_size))
+	}
+
+	/// Runs `query` against the program, returning a lazy iterator over its answers.
+	pub fn query(&mut self, query: &str) -> Result<Solver<'_>> {
+		let goal = self.goal(query)?;
+		let solver = sld_resolution(self.clausifier.get_program(), &self.builtins, &goal)?;
+		let solver = solver.with_limits(self.limits).with_occurs_check(self.occurs_check);
+		Ok(match &self.interrupt {
+			Some(flag) => solver.with_interrupt(flag.clone()),
+			None => solver,
+		})
+	}
+}
+
+/// Parses a query or a formula to prove.
+fn parse_query(query: &str) -> Result<Expression> {
+	let query = query.trim();
+	if query.is_empty() {
+		return Err(GicError::ParseError("Query cannot be empty.".to_string()));
+	}
+
+	let mut formula = query.to_string();
+	formula.push(';');
+
+	parse_formula(&formula)
+}
+
+impl Default for Engine {
+	fn default() -> Self {
+		Engine::new()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::libraries::once;
+	use crate::mgu::bindings::Bindings;
+	use crate::resolution::limits::LimitExceeded;
+	use crate::types::ast::{Proposition, Term};
+
+	#[test]
+	fn test_query_answers() {
+		let mut engine = Engine::new();
+		engine.load_str("Father(juan, pepe). Father(pepe, maria).").unwrap();
+
+		let answers: Vec<String> = engine
+			.query("exists X. Father(juan, X)")
+			.unwrap()
+			.map(|a| a.to_string())
+			.collect();
+
+		assert_eq!(answers, vec!["X := pepe()"]);
+	}
+
+	#[test]
+	fn test_query_is_lazy() {
+		let mut engine = Engine::new();
+		engine.load_str("Nat(0). Nat(N) impl Nat(s(N)).").unwrap();
+
+		let answers: Vec<String> =
+			engine.query("Nat(X)").unwrap().take(3).map(|a| a.to_string()).collect();
+
+		assert_eq!(answers.len(), 3);
+		assert_eq!(answers[0], "X := 0");
+	}
+
+	#[test]
+	fn test_query_chained_builtins() {
+		let mut engine = Engine::new();
+		engine.load_str(include_str!("../../examples/fibonacci.gic")).unwrap();
+
+		let answer = engine.query("Fib(6,F)").unwrap().next().unwrap();
+
+		assert_eq!(answer.get("F"), Some(&Term::Number(8)));
+	}
+
+	#[test]
+	fn test_custom_builtin() {
+		let mut engine = Engine::new();
+		engine.load_str("Person(juan). Person(ana).").unwrap();
+		engine
+			.builtins_mut()
+			.register("Short", 1, |p: &Proposition, bindings: &Bindings| {
+				match bindings.walk(&p.terms[0]) {
+					Term::FunctionApplication { name, .. } if name.len() <= 3 => Some(once(vec![])),
+					_ => None,
+				}
+			});
+
+		let answers: Vec<String> = engine
+			.query("exists X. Person(X) and Short(X)")
+			.unwrap()
+			.map(|a| a.to_string())
+			.collect();
+
+		assert_eq!(answers, vec!["X := ana()"]);
+	}
+
+	#[test]
+	fn test_definitional_cnf() {
+		let mut engine = Engine::new();
+		engine
+			.load_str("(P(a) and Q(a)) or (R(a) and S(a)). forall X. (Q(X) impl T(X)).")
+			.unwrap();
+		engine.set_cnf_mode(CnfMode::Definitional);
+
+		// Queries still see the distributive clauses.
+		assert_eq!(engine.user_program_str().lines().count(), 5);
+		assert_eq!(engine.prove("P(a) or R(a)").unwrap(), ProofResult::Proved);
+		assert_eq!(engine.prove("T(a) or S(a)").unwrap(), ProofResult::Proved);
+		assert_eq!(engine.prove("T(a)").unwrap(), ProofResult::CounterSatisfiable);
+		let ModelResult::Found(model) = engine.model(Some("T(a)"), 2).unwrap() else {
+			panic!("expected a counter-example");
+		};
+		assert!(model.predicates.keys().all(|(name, _)| !name.starts_with('_')));
+	}
+
+	#[test]
+	fn test_load_parse_error() {
+		let mut engine = Engine::new();
+		assert!(matches!(engine.load_str("Father(juan"), Err(GicError::ParseError(_))));
+	}
+
+	#[test]
+	fn test_load_clausify_error_loads_nothing() {
+		let mut engine = Engine::new();
+		let err = engine
+			.load_str("Bird(tweety). :- table Path/2. \\+ Penguin(tweety).")
+			.unwrap_err();
+		assert!(matches!(err, GicError::ClauseError(_)));
+
+		assert!(!engine.has_user_program());
+		assert!(engine.query("Bird(tweety)").unwrap().next().is_none());
+	}
+
+	#[test]
+	fn test_settings_apply_to_queries() {
+		let mut engine = Engine::new();
+		engine.set_limits(Limits { max_steps: Some(1000), ..Limits::unbounded() });
+		engine.set_occurs_check(OccursCheck::On);
+
+		let mut solver = engine.query("Length(XS,N) and Eq(N,-1)").unwrap();
+		assert_eq!(solver.next(), None);
+		assert_eq!(solver.limit_exceeded(), Some(LimitExceeded::Steps(1000)));
+		assert!(engine.query("exists X. X = f(X)").unwrap().next().is_none());
+
+		engine.set_interrupt(Arc::new(AtomicBool::new(true)));
+		let mut solver = engine.query("Length(XS,N)").unwrap();
+		assert_eq!(solver.next(), None);
+		assert!(solver.interrupted());
+	}
+
+	#[test]
+	fn test_interrupted_load_loads_nothing() {
+		let mut engine = Engine::new();
+		engine.set_interrupt(Arc::new(AtomicBool::new(true)));
+
+		assert!(matches!(engine.load_str("Bird(tweety)."), Err(GicError::Interrupted)));
+		assert!(!engine.has_user_program());
+	}
+
+	#[test]
+	fn test_prove_and_model() {
+		let mut engine = Engine::new();
+		engine
+			.load_str("P(a) or Q(a). forall X. (P(X) impl R(X)). forall X. (Q(X) impl R(X)).")
+			.unwrap();
+
+		assert!(engine.query("R(a)").is_err());
+		assert_eq!(engine.prove("R(a)").unwrap(), ProofResult::Proved);
+		assert!(matches!(engine.model(Some("P(a)"), 2).unwrap(), ModelResult::Found(_)));
+		assert_eq!(engine.model(Some("R(a)"), 2).unwrap(), ModelResult::NotFound(2));
+
+		engine.set_limits(Limits { max_steps: Some(0), ..Limits::unbounded() });
+		assert_eq!(engine.prove("R(a)").unwrap(), ProofResult::Unknown(LimitExceeded::Steps(0)));
+	}
+}
@@ -0,0 +1,181 @@
+use std::fmt;
+
+use crate::resolution::answer::Answer;
+use crate::types::ast::Term;
+use crate::types::GicError;
+
+/// A minimal JSON document, used for the machine-readable output of answers and errors.
+#[derive(Debug, Clone, PartialEq)]
+pub enum JsonValue {
+	Null,
+	Bool(bool),
+	Number(i64),
+	String(String),
+	Array(Vec<JsonValue>),
+	Object(Vec<(String, JsonValue)>),
+}
+
+impl JsonValue {
+	pub fn object(fields: Vec<(&str, JsonValue)>) -> Self {
+		JsonValue::Object(fields.into_iter().map(|(k, v)| (k.to_string(), v)).collect())
+	}
+}
+
+impl From<&str> for JsonValue {
+	fn from(s: &str) -> Self {
+		JsonValue::String(s.to_string())
+	}
+}
+
+fn write_json_string(f: &mut fmt::Formatter<'_>, s: &str) -> fmt::Result {
+	write!(f, "\"")?;
+	for c in s.chars() {
+		match c {
+			'"' => write!(f, "\\\"")?,
+			'\\' => write!(f, "\\\\")?,
+			'\n' => write!(f, "\\n")?,
+			'\r' => write!(f, "\\r")?,
+			'\t' => write!(f, "\\t")?,
+			c if (c as u32) < 0x20 => write!(f, "\\u{:04x}", c as u32)?,
+			c => write!(f, "{}", c)?,
+		}
+	}
+	write!(f, "\"")
+}
+
+impl fmt::Display for JsonValue {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			JsonValue::Null => write!(f, "null"),
+			JsonValue::Bool(b) => write!(f, "{}", b),
+			JsonValue::Number(n) => write!(f, "{}", n),
+			JsonValue::String(s) => write_json_string(f, s),
+			JsonValue::Array(items) => {
+				write!(f, "[")?;
+				for (i, item) in items.iter().enumerate() {
+					if i > 0 {
+						write!(f, ",")?;
+					}
+					write!(f, "{}", item)?;
+				}
+				write!(f, "]")
+			},
+			JsonValue::Object(fields) => {
+				write!(f, "{{")?;
+				for (i, (key, value)) in fields.iter().enumerate() {
+					if i > 0 {
+						write!(f, ",")?;
+					}
+					write_json_string(f, key)?;
+					write!(f, ":{}", value)?;
+				}
+				write!(f, "}}")
+			},
+		}
+	}
+}
+
+pub trait ToJson {
+	fn to_json(&self) -> JsonValue;
+}
+
+/// Constants become strings, numbers become numbers and proper lists become arrays. Variables are
+/// written as `{"var": name}` and other terms as `{"functor": name, "args": [...]}`, while lists
+/// with an open tail are written as `{"list": [...], "tail": term}`.
+impl ToJson for Term {
+	fn to_json(&self) -> JsonValue {
+		match self {
+			Term::Identifier(id) => JsonValue::object(vec![("var", id.as_str().into())]),
+			Term::Var(_) => JsonValue::object(vec![("var", self.to_string().as_str().into())]),
+			Term::Number(n) => JsonValue::Number(*n),
+			Term::FunctionApplication { name, args } if name == "empty_list" && args.is_empty() => {
+				JsonValue::Array(vec![])
+			},
+			Term::FunctionApplication { name, args } if name == "cons" && args.len() == 2 => {
+				let mut items = vec![args[0].to_json()];
+				let mut tail = &args[1];
+				while let Term::FunctionApplication { name, args } = tail {
+					if name == "cons" && args.len() == 2 {
+						items.push(args[0].to_json());
+						tail = &args[1];
+					} else if name == "empty_list" && args.is_empty() {
+						return JsonValue::Array(items);
+					} else {
+						break;
+					}
+				}
+				JsonValue::object(vec![("list", JsonValue::Array(items)), ("tail", tail.to_json())])
+			},
+			Term::FunctionApplication { name, args } if args.is_empty() => {
+				JsonValue::String(name.clone())
+			},
+			Term::FunctionApplication { name, args } => JsonValue::object(vec![
+				("functor", name.as_str().into()),
+				("args", JsonValue::Array(args.iter().map(|a| a.to_json()).collect())),
+			]),
+		}
+	}
+}
+
+/// An object mapping each variable of the query to its value.
+impl ToJson for Answer {
+	fn to_json(&self) -> JsonValue {
+		JsonValue::Object(
+			self.bindings
+				.iter()
+				.map(|(var, value)| (var.clone(), value.to_json()))
+				.collect(),
+		)
+	}
+}
+
+impl ToJson for GicError {
+	fn to_json(&self) -> JsonValue {
+		JsonValue::object(vec![("kind", self.kind().into()), ("message", self.message().into())])
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::parser::parse_gic_file;
+	use crate::types::ast::Expression;
+
+	fn term(input: &str) -> Term {
+		let mut exprs = parse_gic_file(&format!("P({}).", input)).unwrap();
+		match exprs.pop().unwrap() {
+			Expression::Proposition(prop) => prop.terms.into_iter().next().unwrap(),
+			_ => panic!("Expected proposition"),
+		}
+	}
+
+	#[test]
+	fn test_term_to_json() {
+		assert_eq!(term("juan").to_json().to_string(), r#""juan""#);
+		assert_eq!(term("-3").to_json().to_string(), "-3");
+		assert_eq!(term("X").to_json().to_string(), r#"{"var":"X"}"#);
+		assert_eq!(
+			term("f(a, 1, Y)").to_json().to_string(),
+			r#"{"functor":"f","args":["a",1,{"var":"Y"}]}"#
+		);
+	}
+
+	#[test]
+	fn test_list_to_json() {
+		assert_eq!(term("[]").to_json().to_string(), "[]");
+		assert_eq!(term("[1, [a], X]").to_json().to_string(), r#"[1,["a"],{"var":"X"}]"#);
+		assert_eq!(term("[1|T]").to_json().to_string(), r#"{"list":[1],"tail":{"var":"T"}}"#);
+	}
+
+	#[test]
+	fn test_escape() {
+		let value = JsonValue::from("say \"hi\"\n\\");
+		assert_eq!(value.to_string(), r#""say \"hi\"\n\\""#);
+	}
+
+	#[test]
+	fn test_error_to_json() {
+		let error = GicError::ClauseError("bad".to_string());
+		assert_eq!(error.to_json().to_string(), r#"{"kind":"clause","message":"bad"}"#);
+	}
+}
@@ -0,0 +1,51 @@
+// gic
+// Copyright (C) 2025 Lucas Grasso
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! The GIC engine: parsing, clausification, unification and SLD resolution for
+//! First Order Logic programs.
+//!
+//! ```
+//! use gic::Engine;
+//!
+//! let mut engine = Engine::new();
+//! engine.load_str("Father(juan, pepe). Father(pepe, maria).").unwrap();
+//! for answer in engine.query("exists X. Father(juan, X)").unwrap() {
+//!     println!("{}", answer);
+//! }
+//! ```
+
+pub mod clauses;
+pub mod engine;
+pub mod json;
+pub mod libraries;
+pub mod mgu;
+pub mod models;
+pub mod parser;
+pub mod resolution;
+pub mod types;
+
+pub use clauses::cnf::{Clausifier, CnfMode};
+pub use engine::Engine;
+pub use mgu::mgu::{mgu, OccursCheck};
+pub use models::finder::{Model, ModelFinder, ModelResult};
+pub use parser::{parse_formula, parse_gic_file, parse_gic_program};
+pub use resolution::{
+	answer::Answer,
+	limits::{LimitExceeded, Limits},
+	resolution::{sld_resolution, Solver},
+	saturation::{ProofResult, Prover},
+};
+pub use types::{GicError, Result};
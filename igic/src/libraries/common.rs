@@ -1,53 +1,56 @@
-use crate::mgu::mgu::{Substitution, Unifiable};
-use crate::mgu::substitution::apply_substitution;
+use crate::libraries::registry::{once, Branches};
+use crate::mgu::bindings::Bindings;
 use crate::types::ast::{Proposition, Term};
-use crate::types::clause::Clause;
 
-use std::iter;
-
-pub fn eq_pred(
-	sub: &Substitution,
-	p: &Proposition,
-	goal: &Clause,
-) -> Option<Box<dyn Iterator<Item = (Clause, Substitution)>>> {
-	let a = apply_substitution(sub, &Unifiable::Term(p.terms[0].clone()));
-	let b = apply_substitution(sub, &Unifiable::Term(p.terms[1].clone()));
+pub fn eq_pred(p: &Proposition, bindings: &Bindings) -> Option<Branches> {
+	let a = bindings.resolve(&p.terms[0]);
+	let b = bindings.resolve(&p.terms[1]);
 	if a == b {
-		let mut rem = goal.0.clone();
-		rem.remove(0);
-		Some(Box::new(iter::once((Clause(rem), sub.clone()))))
+		Some(once(vec![]))
 	} else {
 		None
 	}
 }
 
-pub fn diff_pred(
-	sub: &Substitution,
-	p: &Proposition,
-	goal: &Clause,
-) -> Option<Box<dyn Iterator<Item = (Clause, Substitution)>>> {
-	let a = apply_substitution(sub, &Unifiable::Term(p.terms[0].clone()));
-	let b = apply_substitution(sub, &Unifiable::Term(p.terms[1].clone()));
+pub fn diff_pred(p: &Proposition, bindings: &Bindings) -> Option<Branches> {
+	let a = bindings.resolve(&p.terms[0]);
+	let b = bindings.resolve(&p.terms[1]);
 	if a != b {
-		let mut rem = goal.0.clone();
-		rem.remove(0);
-		Some(Box::new(iter::once((Clause(rem), sub.clone()))))
+		Some(once(vec![]))
 	} else {
 		None
 	}
 }
 
-pub fn var_pred(
-	sub: &Substitution,
-	p: &Proposition,
-	goal: &Clause,
-) -> Option<Box<dyn Iterator<Item = (Clause, Substitution)>>> {
-	match apply_substitution(sub, &Unifiable::Term(p.terms[0].clone())) {
-		Unifiable::Term(Term::Identifier(_)) => {
-			let mut rem = goal.0.clone();
-			rem.remove(0);
-			Some(Box::new(iter::once((Clause(rem), sub.clone()))))
-		},
+pub fn var_pred(p: &Proposition, bindings: &Bindings) -> Option<Branches> {
+	match bindings.walk(&p.terms[0]) {
+		Term::Var(_) => Some(once(vec![])),
 		_ => None,
 	}
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn eq(a: Term, b: Term) -> Proposition {
+		Proposition { name: "Eq".to_string(), terms: vec![a, b] }
+	}
+
+	fn constant(name: &str) -> Term {
+		Term::FunctionApplication { name: name.to_string(), args: vec![] }
+	}
+
+	#[test]
+	fn test_eq_does_not_bind() {
+		let mut bindings = Bindings::new();
+		let f_a = Term::FunctionApplication { name: "f".to_string(), args: vec![constant("a")] };
+
+		assert!(eq_pred(&eq(f_a.clone(), f_a), &bindings).is_some());
+		assert!(eq_pred(&eq(Term::Var(0), constant("a")), &bindings).is_none());
+		assert_eq!(bindings.get(0), None);
+
+		bindings.bind(0, constant("a"));
+		assert!(eq_pred(&eq(Term::Var(0), constant("a")), &bindings).is_some());
+	}
+}
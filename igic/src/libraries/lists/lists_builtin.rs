@@ -1,22 +1,10 @@
-use crate::mgu::mgu::{Substitution, Unifiable};
-use crate::mgu::substitution::{
-	apply_substitution, apply_substitution_to_clause, compose_substitutions, empty_substitution,
-};
+use crate::libraries::registry::{once, Branches};
+use crate::mgu::bindings::Bindings;
 use crate::types::ast::{Proposition, Term};
-use crate::types::clause::Clause;
 
-pub fn is_list_pred(
-	goal: &Clause,
-	p: &Proposition,
-	sub: &Substitution,
-) -> Option<Box<dyn Iterator<Item = (Clause, Substitution)>>> {
-	let term = apply_substitution(sub, &Unifiable::Term(p.terms[0].clone()));
-	if let Unifiable::Term(t) = term {
-		if is_proper_list(&t) {
-			let mut rem = goal.0.clone();
-			rem.remove(0);
-			return Some(Box::new(std::iter::once((Clause(rem), sub.clone()))));
-		}
+pub fn is_list_pred(p: &Proposition, bindings: &Bindings) -> Option<Branches> {
+	if is_proper_list(&bindings.resolve(&p.terms[0])) {
+		return Some(once(vec![]));
 	}
 	None
 }
@@ -36,98 +24,34 @@ fn is_proper_list(term: &Term) -> bool {
 	}
 }
 
-pub fn length_pred<'a>(
-	goal: &'a Clause,
-	prop: &'a Proposition,
-	sub: &'a Substitution,
-) -> Option<Box<dyn Iterator<Item = (Clause, Substitution)> + 'a>> {
-	let list_term = apply_substitution(sub, &Unifiable::Term(prop.terms[0].clone()));
-	let len_term = apply_substitution(sub, &Unifiable::Term(prop.terms[1].clone()));
+pub fn length_pred(prop: &Proposition, bindings: &Bindings) -> Option<Branches> {
+	let list_term = bindings.resolve(&prop.terms[0]);
+	let len_term = bindings.walk(&prop.terms[1]).clone();
 
 	match (list_term, len_term) {
-		// Case: both list and length are instanciated
-		(
-			Unifiable::Term(Term::FunctionApplication { name, args }),
-			Unifiable::Term(Term::Number(n)),
-		) => {
-			let l_term = Unifiable::Term(Term::FunctionApplication { name, args });
-			if let Some(true_length) = get_length_of_list(&l_term) {
-				if true_length == n as usize {
-					let new_goal = Clause(goal.0[1..].to_vec());
-					let new_sub = sub.clone();
-					return Some(Box::new(std::iter::once((new_goal, new_sub))));
-				}
-			}
-			None
-		},
-		// Case: lists is instanciated, length is free
-		(
-			Unifiable::Term(Term::FunctionApplication { name, args }),
-			Unifiable::Term(Term::Identifier(_)),
-		) => {
-			let l_term = Unifiable::Term(Term::FunctionApplication { name, args });
-			if let Some(true_length) = get_length_of_list(&l_term) {
-				let mut temp_sub = empty_substitution();
-				temp_sub.insert(
-					Unifiable::Term(prop.terms[1].clone()),
-					Unifiable::Term(Term::Number(true_length as i64)),
-				);
-
-				let mut new_goal = Clause(goal.0[1..].to_vec());
-				let mut new_sub = sub.clone();
-				compose_substitutions(&temp_sub, &mut new_sub);
-				apply_substitution_to_clause(&temp_sub, &mut new_goal);
-
-				return Some(Box::new(std::iter::once((new_goal, new_sub))));
-			}
-			None
+		// Case: the list is instanciated, the length is checked or bound
+		(list @ Term::FunctionApplication { .. }, len) => {
+			let true_length = get_length_of_list(&list)?;
+			Some(once(vec![(len, Term::Number(true_length as i64))]))
 		},
 		// Case: length is a concrete number, we generate that many empty cons cells
-		(Unifiable::Term(Term::Identifier(_)), Unifiable::Term(Term::Number(n))) => {
-			let list = generate_list_of_length(n as usize);
-			let mut temp_sub = empty_substitution();
-			temp_sub.insert(Unifiable::Term(prop.terms[0].clone()), Unifiable::Term(list));
-
-			let mut new_goal = Clause(goal.0[1..].to_vec());
-			let mut new_sub = sub.clone();
-			compose_substitutions(&temp_sub, &mut new_sub);
-			apply_substitution_to_clause(&temp_sub, &mut new_goal);
-
-			Some(Box::new(std::iter::once((new_goal, new_sub))))
-		},
-		(Unifiable::Term(Term::Identifier(_)), Unifiable::Term(Term::Identifier(_))) => {
-			Some(Box::new((0..).map(move |n| {
-				let list = generate_list_of_length(n as usize);
-
-				let mut temp_sub = empty_substitution();
-				temp_sub.insert(Unifiable::Term(prop.terms[0].clone()), Unifiable::Term(list));
-				temp_sub.insert(
-					Unifiable::Term(prop.terms[1].clone()),
-					Unifiable::Term(Term::Number(n)),
-				);
-
-				let mut new_goal = Clause(goal.0[1..].to_vec());
-				let mut new_sub = sub.clone();
-
-				compose_substitutions(&temp_sub, &mut new_sub);
-				apply_substitution_to_clause(&temp_sub, &mut new_goal);
-
-				(new_goal, new_sub)
-			})))
+		(list @ Term::Var(_), Term::Number(n)) if n >= 0 => {
+			Some(once(vec![(list, generate_list_of_length(n as usize))]))
 		},
+		(list @ Term::Var(_), len @ Term::Var(_)) => Some(Box::new((0..).map(move |n| {
+			vec![(list.clone(), generate_list_of_length(n)), (len.clone(), Term::Number(n as i64))]
+		}))),
 		_ => None,
 	}
 }
 
-fn get_length_of_list(term: &Unifiable) -> Option<usize> {
+fn get_length_of_list(term: &Term) -> Option<usize> {
 	match term {
-		Unifiable::Term(Term::FunctionApplication { name, args }) => {
+		Term::FunctionApplication { name, args } => {
 			if name == "empty_list" && args.is_empty() {
 				Some(0)
 			} else if name == "cons" && args.len() == 2 {
-				let tail = &args[1];
-				let unifiable_tail = Unifiable::Term(tail.clone());
-				get_length_of_list(&unifiable_tail).map(|len| len + 1)
+				get_length_of_list(&args[1]).map(|len| len + 1)
 			} else {
 				None
 			}
@@ -1,50 +1,69 @@
 pub mod common;
 pub mod integers;
 pub mod lists;
+pub mod registry;
 
-use lists::lists_builtin::is_list_pred;
+pub use registry::{once, Branch, Branches, BuiltinPredicate, BuiltinRegistry};
 
 use crate::libraries::common::*;
 use crate::libraries::integers::arithmetic::*;
 use crate::libraries::integers::comparation::*;
 use crate::libraries::lists::lists_builtin::*;
+use crate::mgu::bindings::Bindings;
+use crate::types::ast::Proposition;
 
-use crate::mgu::mgu::Substitution;
-use crate::types::clause::{Clause, Literal};
-
-pub fn built_in_preds<'a>(
-	goal: &'a Clause,
-	lit: &'a Literal,
-	sub: &'a Substitution,
-) -> Option<Box<dyn Iterator<Item = (Clause, Substitution)> + 'a>> {
-	if let Literal::Not(p) = lit {
-		match (p.name.as_str(), p.terms.len()) {
-			("Eq", 2) => return eq_pred(sub, p, goal),
-			("Diff", 2) => return diff_pred(sub, p, goal),
-			("Var", 1) => return var_pred(sub, p, goal),
-			("Add", 3) => return arithmetic_op_pred(goal, p, sub, |a, b| a + b),
-			("Sub", 3) => return arithmetic_op_pred(goal, p, sub, |a, b| a - b),
-			("Mul", 3) => return arithmetic_op_pred(goal, p, sub, |a, b| a * b),
-			("Div", 3) => {
-				return arithmetic_op_pred(goal, p, sub, |a, b| {
-					if b == 0 {
-						panic!("Division by zero");
-					}
-					a / b
-				});
-			},
-			("Mod", 3) => return arithmetic_op_pred(goal, p, sub, |a, b| a % b),
-			("Lt", 2) => return compare_pred(goal, p, sub, |a, b| a < b),
-			("Lt_eq", 2) => return compare_pred(goal, p, sub, |a, b| a <= b),
-			("Gt", 2) => return compare_pred(goal, p, sub, |a, b| a > b),
-			("Gt_eq", 2) => return compare_pred(goal, p, sub, |a, b| a >= b),
-			("Eq_int", 2) => return compare_pred(goal, p, sub, |a, b| a == b),
-			("Diff_int", 2) => return compare_pred(goal, p, sub, |a, b| a != b),
-			("Between", 3) => return between_pred(goal, p, sub),
-			("Is_list", 1) => return is_list_pred(goal, p, sub),
-			("Length", 2) => return length_pred(goal, p, sub),
-			_ => return None,
-		}
-	}
-	None
+/// Returns a registry holding the predicates of `BuiltIns.md` that are implemented in Rust. `=` is
+/// defined in `common.gic` instead, so that it unifies with the occurs check of the query.
+pub fn standard_builtins() -> BuiltinRegistry {
+	let mut registry = BuiltinRegistry::new();
+
+	registry.register("Eq", 2, eq_pred);
+	registry.register("Diff", 2, diff_pred);
+	registry.register("Var", 1, var_pred);
+
+	registry.register("Add", 3, |p: &Proposition, bindings: &Bindings| {
+		arithmetic_op_pred(p, bindings, |a, b| a + b)
+	});
+	registry.register("Sub", 3, |p: &Proposition, bindings: &Bindings| {
+		arithmetic_op_pred(p, bindings, |a, b| a - b)
+	});
+	registry.register("Mul", 3, |p: &Proposition, bindings: &Bindings| {
+		arithmetic_op_pred(p, bindings, |a, b| a * b)
+	});
+	registry.register("Div", 3, |p: &Proposition, bindings: &Bindings| {
+		arithmetic_op_pred(p, bindings, |a, b| {
+			if b == 0 {
+				panic!("Division by zero");
+			}
+			a / b
+		})
+	});
+	registry.register("Mod", 3, |p: &Proposition, bindings: &Bindings| {
+		arithmetic_op_pred(p, bindings, |a, b| a % b)
+	});
+
+	registry.register("Lt", 2, |p: &Proposition, bindings: &Bindings| {
+		compare_pred(p, bindings, |a, b| a < b)
+	});
+	registry.register("Lt_eq", 2, |p: &Proposition, bindings: &Bindings| {
+		compare_pred(p, bindings, |a, b| a <= b)
+	});
+	registry.register("Gt", 2, |p: &Proposition, bindings: &Bindings| {
+		compare_pred(p, bindings, |a, b| a > b)
+	});
+	registry.register("Gt_eq", 2, |p: &Proposition, bindings: &Bindings| {
+		compare_pred(p, bindings, |a, b| a >= b)
+	});
+	registry.register("Eq_int", 2, |p: &Proposition, bindings: &Bindings| {
+		compare_pred(p, bindings, |a, b| a == b)
+	});
+	registry.register("Diff_int", 2, |p: &Proposition, bindings: &Bindings| {
+		compare_pred(p, bindings, |a, b| a != b)
+	});
+	registry.register("Between", 3, between_pred);
+
+	registry.register("Is_list", 1, is_list_pred);
+	registry.register("Length", 2, length_pred);
+
+	registry
 }
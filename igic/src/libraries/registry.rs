@@ -0,0 +1,71 @@
+use std::collections::HashMap;
+use std::iter;
+
+use crate::mgu::bindings::Bindings;
+use crate::types::ast::{Proposition, Term};
+use crate::types::clause::Literal;
+
+/// One way of satisfying a builtin predicate: pairs of terms that the solver unifies before going
+/// on with the rest of the goal. An empty branch succeeds without binding anything.
+pub type Branch = Vec<(Term, Term)>;
+
+/// The alternatives produced by a builtin predicate, pulled one at a time when backtracking.
+pub type Branches = Box<dyn Iterator<Item = Branch>>;
+
+/// The branches of a predicate that succeeds exactly once, after unifying `equations`.
+pub fn once(equations: Branch) -> Branches {
+	Box::new(iter::once(equations))
+}
+
+/// A predicate implemented in Rust.
+///
+/// `prop` is the call and `bindings` holds the values of its variables, which can be read with
+/// [`Bindings::resolve`]. Returns `None` when the predicate fails.
+pub trait BuiltinPredicate {
+	fn call(&self, prop: &Proposition, bindings: &Bindings) -> Option<Branches>;
+}
+
+impl<F> BuiltinPredicate for F
+where
+	F: Fn(&Proposition, &Bindings) -> Option<Branches>,
+{
+	fn call(&self, prop: &Proposition, bindings: &Bindings) -> Option<Branches> {
+		self(prop, bindings)
+	}
+}
+
+/// Builtin predicates keyed by name and arity.
+#[derive(Default)]
+pub struct BuiltinRegistry {
+	preds: HashMap<(String, usize), Box<dyn BuiltinPredicate>>,
+}
+
+impl BuiltinRegistry {
+	pub fn new() -> Self {
+		BuiltinRegistry { preds: HashMap::new() }
+	}
+
+	/// Registers `pred` as `name/arity`, replacing any previous predicate with that signature.
+	pub fn register<P>(&mut self, name: &str, arity: usize, pred: P)
+	where
+		P: BuiltinPredicate + 'static,
+	{
+		self.preds.insert((name.to_string(), arity), Box::new(pred));
+	}
+
+	pub fn contains(&self, name: &str, arity: usize) -> bool {
+		self.preds.contains_key(&(name.to_string(), arity))
+	}
+
+	/// Runs the builtin called by `lit`, the first literal of a goal.
+	///
+	/// Returns `None` if `lit` does not call a registered builtin, so it must be resolved against
+	/// the program instead. A builtin that fails yields no branches.
+	pub fn call(&self, lit: &Literal, bindings: &Bindings) -> Option<Branches> {
+		if let Literal::Not(p) = lit {
+			let pred = self.preds.get(&(p.name.clone(), p.terms.len()))?;
+			return Some(pred.call(p, bindings).unwrap_or_else(|| Box::new(iter::empty())));
+		}
+		None
+	}
+}
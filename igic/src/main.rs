@@ -14,32 +14,60 @@
 // You should have received a copy of the GNU General Public License
 // along with this program. If not, see <https://www.gnu.org/licenses/>.
 use colored::*;
-use std::env;
-use std::fs;
+use regex::Regex;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use std::{env, process};
 
-use crate::clauses::cnf::Clausifier;
-use crate::parser::{parse_formula, parse_gic_file};
+use gic::models::dpll::Satisfiability;
+use gic::models::propositional::{
+	falsifying_valuation_until, parse_propositional, satisfying_valuation_until, truth_table,
+};
+use gic::{Engine, GicError, ModelResult, ProofResult, Solver};
 use rustyline::error::ReadlineError;
 use rustyline::history::FileHistory;
 use rustyline::Editor;
 
-mod clauses;
-mod libraries;
-mod mgu;
-mod parser;
-mod resolution;
-mod types;
+mod cli;
+
+/// The largest domain searched by the `model` command when no size is given.
+const DEFAULT_MODEL_SIZE: usize = 5;
 
 fn main() {
+	let args: Vec<String> = env::args().skip(1).collect();
+	match args.first().map(String::as_str) {
+		None => repl(),
+		Some("run") => process::exit(cli::run(&args[1..])),
+		Some("-h" | "--help") => println!("{}", cli::USAGE),
+		Some(other) => {
+			eprintln!("Unknown command: '{}'\n\n{}", other, cli::USAGE);
+			process::exit(cli::EXIT_ERROR);
+		},
+	}
+}
+
+fn repl() {
 	let mut rl = rustyline::DefaultEditor::new().unwrap();
-	let mut clausifier = Clausifier::new();
+	let mut engine = Engine::new();
+
+	// Ctrl-C while a command runs only stops that command. At the prompt it is handled by rustyline.
+	let ctrl_c = CtrlC::default();
+	let handler = ctrl_c.clone();
+	match ctrlc::set_handler(move || handler.signal()) {
+		Ok(()) => engine.set_interrupt(ctrl_c.interrupt.clone()),
+		Err(e) => {
+			eprint!("{}", "Warning: ".yellow());
+			eprintln!("Could not install the Ctrl-C handler: {}", e);
+		},
+	}
 
 	let cwd = env::current_dir().unwrap_or_else(|_| {
 		eprintln!("Error getting current directory, using default.");
 		env::current_dir().unwrap()
 	});
 
-	let progam_index = load_common_libraries(&mut clausifier);
+	let query_re = Regex::new(r#""(.*?)""#).unwrap();
 
 	println!("Welcome to the IGIC REPL! Type 'exit' or 'quit' to leave.");
 	let history_path = "igic_history.txt";
@@ -63,23 +91,24 @@ fn main() {
 				let command = parts.next().unwrap_or("");
 
 				match command {
-					"load" => load_cmd(&mut clausifier, &cwd, parts.next().unwrap_or("")),
+					"load" => {
+						let file = parts.next().unwrap_or("");
+						ctrl_c.run(|| load_cmd(&mut engine, &cwd, file));
+					},
 					"program" => {
-						if clausifier.get_progam_length() - progam_index >= 1 {
-							println!("{}", clausifier.to_str_from(progam_index));
+						if engine.has_user_program() {
+							println!("{}", engine.user_program_str());
 						} else {
 							eprint!("{}", "Warning: ".yellow());
 							eprintln!("No program loaded. Please load a .gic file first.");
 						}
 					},
 					"query" => {
-						use regex::Regex;
 						let rest_of_line = parts.collect::<Vec<&str>>().join(" ");
 
-						let re = Regex::new(r#""(.*?)""#).unwrap();
-						if let Some(caps) = re.captures(&rest_of_line) {
+						if let Some(caps) = query_re.captures(&rest_of_line) {
 							let query_input = caps.get(1).unwrap().as_str();
-							query_cmd(&mut clausifier, query_input, &mut rl);
+							ctrl_c.run(|| query_cmd(&mut engine, query_input, &mut rl));
 						} else {
 							eprint!("{}", "Error: ".red());
 							eprintln!(
@@ -87,12 +116,81 @@ fn main() {
 							);
 						}
 					},
+					"prove" => {
+						let rest_of_line = parts.collect::<Vec<&str>>().join(" ");
+
+						if let Some(caps) = query_re.captures(&rest_of_line) {
+							ctrl_c.run(|| prove_cmd(&mut engine, caps.get(1).unwrap().as_str()));
+						} else {
+							eprint!("{}", "Error: ".red());
+							eprintln!(
+								"Formula must be wrapped in double quotes, like: prove \"<formula>\""
+							);
+						}
+					},
+					"model" => {
+						let rest_of_line = parts.collect::<Vec<&str>>().join(" ");
+						let (formula, size) = match query_re.captures(&rest_of_line) {
+							Some(caps) => (
+								Some(caps.get(1).unwrap().as_str()),
+								rest_of_line[caps.get(0).unwrap().end()..].trim(),
+							),
+							None => (None, rest_of_line.trim()),
+						};
+						let max_size = match size {
+							"" => Some(DEFAULT_MODEL_SIZE),
+							size => size.parse().ok().filter(|&n| n > 0),
+						};
+						match max_size {
+							Some(max_size) => {
+								ctrl_c.run(|| model_cmd(&mut engine, formula, max_size));
+							},
+							None => {
+								eprint!("{}", "Error: ".red());
+								eprintln!(
+									"The domain size must be a positive number, like: model \"<formula>\" 4"
+								);
+							},
+						}
+					},
+					"truthtable" | "sat" | "valid" => {
+						let rest_of_line = parts.collect::<Vec<&str>>().join(" ");
+
+						if let Some(caps) = query_re.captures(&rest_of_line) {
+							let formula = caps.get(1).unwrap().as_str();
+							ctrl_c.run(|| propositional_cmd(command, formula, &ctrl_c.interrupt));
+						} else {
+							eprint!("{}", "Error: ".red());
+							eprintln!(
+								"Formula must be wrapped in double quotes, like: {} \"<formula>\"",
+								command
+							);
+						}
+					},
+					"set" => set_cmd(&mut engine, parts.next(), parts.next()),
 					"help" | "h" => {
 						println!(
 							"Available commands:\n\
 							- load <file>: Load a GIC file.\n\
 							- query \"<expr>\": Query the program with a formula.\n\
+							- prove \"<formula>\": Prove that a formula follows from the program,\n\
+							which need not be Horn, with a saturation prover.\n\
+							- model [\"<formula>\"] [<size>]: Find a finite model of the program,\n\
+							and of the negation of the formula if given, with up to <size> elements.\n\
+							- truthtable \"<formula>\": Show the truth table of a propositional formula,\n\
+							whose predicates have no arguments, like \"P impl (Q or P)\".\n\
+							- sat \"<formula>\": Find a valuation satisfying a propositional formula.\n\
+							- valid \"<formula>\": Check that a propositional formula is valid, or find\n\
+							a valuation falsifying it.\n\
 							- program: Show the current program.\n\
+							- set: Show the current settings.\n\
+							- set <setting> <value|off>: Change a setting: depth (derivation depth),\n\
+							steps (inference steps) or timeout (seconds per query).\n\
+							- set occurs <off|on|error>: Unify without the occurs check, fail on\n\
+							cyclic terms like X = f(X), or stop the query with an error.\n\
+							- set cnf <distributive|definitional>: How prove and model turn the\n\
+							program into clauses. Definitional CNF names subformulas to avoid an\n\
+							exponential number of clauses.\n\
 							- exit or quit: Exit the REPL."
 						);
 					},
@@ -106,7 +204,7 @@ fn main() {
 				}
 			},
 			Err(ReadlineError::Interrupted) => {
-				break;
+				continue;
 			},
 			Err(ReadlineError::Eof) => {
 				break;
@@ -120,27 +218,26 @@ fn main() {
 	rl.save_history(history_path).unwrap();
 }
 
-fn load_common_libraries(clausifier: &mut Clausifier) -> usize {
-	let libraries: &[(&str, &str)] =
-		&[("lists.gic", include_str!("../src/libraries/lists/lists.gic"))];
-
-	for (name, content) in libraries {
-		match parse_gic_file(content) {
-			Ok(expressions) => {
-				for expr in expressions {
-					if let Err(e) = clausifier.add_to_program(expr) {
-						eprintln!("{}", format!("Error loading library {}: {}", name, e).red());
-					}
-				}
-			},
-			Err(e) => eprintln!("{}", format!("Parse error in library {}: {}", name, e).red()),
-		}
+/// What Ctrl-C does outside the prompt: it sets `interrupt`, which the running command polls to
+/// stop early. It never terminates igic, so the loaded program and the history are kept.
+#[derive(Clone, Default)]
+struct CtrlC {
+	interrupt: Arc<AtomicBool>,
+}
+
+impl CtrlC {
+	fn signal(&self) {
+		self.interrupt.store(true, Ordering::SeqCst);
 	}
 
-	clausifier.get_progam_length()
+	/// Runs `command`, forgetting any Ctrl-C pressed before it started.
+	fn run<T>(&self, command: impl FnOnce() -> T) -> T {
+		self.interrupt.store(false, Ordering::SeqCst);
+		command()
+	}
 }
 
-fn load_cmd(clausifier: &mut Clausifier, cwd: &std::path::Path, input: &str) {
+fn load_cmd(engine: &mut Engine, cwd: &std::path::Path, input: &str) {
 	let filename = cwd.join(input);
 
 	// Check if the filename ends with .gic
@@ -149,51 +246,202 @@ fn load_cmd(clausifier: &mut Clausifier, cwd: &std::path::Path, input: &str) {
 		return;
 	}
 
-	match fs::read_to_string(&filename) {
-		Ok(content) => match parse_gic_file(&content) {
-			Ok(expressions) => {
-				for expr in expressions {
-					if let Err(e) = clausifier.add_to_program(expr) {
-						eprintln!("{}", format!("Error clausifying: {}", e).red());
-					}
-				}
-				println!("{}", "loaded.".green());
-			},
-			Err(e) => eprintln!("{}", format!("Parse error: {}", e).red()),
-		},
+	match engine.load_file(&filename) {
+		Ok(()) => println!("{}", "loaded.".green()),
+		Err(GicError::Interrupted) => eprintln!("{}", "Interrupted, nothing loaded.".yellow()),
+		Err(e) => eprintln!(
+			"{}",
+			format!("Error loading file '{}': {}", filename.to_string_lossy(), e).red()
+		),
+	}
+}
+
+fn query_cmd(engine: &mut Engine, input: &str, rl: &mut Editor<(), FileHistory>) {
+	let solver = match engine.query(input) {
+		Ok(solver) => solver,
 		Err(e) => {
-			eprintln!("Error reading file '{}': {}", filename.to_string_lossy(), e)
+			eprintln!("{}", e.to_string().red());
+			return;
+		},
+	};
+
+	let mut solver = solver;
+	let mut warnings_shown = 0;
+	while let Some(answer) = solver.next() {
+		print_warnings(&solver, &mut warnings_shown);
+		if answer.is_empty() {
+			println!("{}", answer.to_string().green());
+		} else {
+			println!("{}", answer);
+		}
+		if !continue_prompt(rl) {
+			return;
+		}
+	}
+	print_warnings(&solver, &mut warnings_shown);
+	if solver.interrupted() {
+		eprintln!("{}", "Interrupted.".yellow());
+		return;
+	}
+	if let Some(e) = solver.error() {
+		eprintln!("{}", e.to_string().red());
+		return;
+	}
+	match solver.limit_exceeded() {
+		Some(limit) => {
+			eprint!("{}", "Resource limit exceeded: ".yellow());
+			eprintln!("{}.", limit);
 		},
+		None => println!("{}", "false.".red()),
 	}
 }
 
-fn query_cmd(clausifier: &mut Clausifier, input: &str, rl: &mut Editor<(), FileHistory>) {
-	let query = input.trim();
+fn prove_cmd(engine: &mut Engine, input: &str) {
+	match engine.prove(input) {
+		Ok(result @ ProofResult::Proved) => println!("{}", result.to_string().green()),
+		Ok(result @ ProofResult::CounterSatisfiable) => println!("{}", result.to_string().red()),
+		Ok(result @ ProofResult::Unknown(limit)) => {
+			println!("{}", result.to_string().yellow());
+			eprint!("{}", "Resource limit exceeded: ".yellow());
+			eprintln!("{}.", limit);
+		},
+		Ok(ProofResult::Interrupted) => eprintln!("{}", "Interrupted.".yellow()),
+		Err(e) => eprintln!("{}", e.to_string().red()),
+	}
+}
 
-	if query.is_empty() {
-		eprintln!("Error: Query cannot be empty.");
-		return;
+fn model_cmd(engine: &mut Engine, formula: Option<&str>, max_size: usize) {
+	match engine.model(formula, max_size) {
+		Ok(ModelResult::Found(model)) => println!("{}", model),
+		Ok(result @ ModelResult::NotFound(_)) => println!("{}", result.to_string().red()),
+		Ok(result @ ModelResult::Unknown(limit)) => {
+			println!("{}", result.to_string().yellow());
+			eprint!("{}", "Resource limit exceeded: ".yellow());
+			eprintln!("{}.", limit);
+		},
+		Ok(ModelResult::Interrupted) => eprintln!("{}", "Interrupted.".yellow()),
+		Err(e) => eprintln!("{}", e.to_string().red()),
+	}
+}
+
+/// Runs the `truthtable`, `sat` or `valid` command on a propositional formula. The searches of
+/// `sat` and `valid` stop when `interrupt` is set.
+fn propositional_cmd(command: &str, input: &str, interrupt: &AtomicBool) {
+	let expr = match parse_propositional(input) {
+		Ok(expr) => expr,
+		Err(e) => {
+			eprintln!("{}", e.to_string().red());
+			return;
+		},
+	};
+	let stop = || interrupt.load(Ordering::Relaxed);
+	let result = match command {
+		"truthtable" => truth_table(&expr).map(|table| println!("{}", table)),
+		"sat" => satisfying_valuation_until(&expr, stop).map(|result| match result {
+			Satisfiability::Satisfiable(valuation) => {
+				println!("{}\n{}", "satisfiable:".green(), valuation)
+			},
+			Satisfiability::Unsatisfiable => println!("{}", "unsatisfiable.".red()),
+			Satisfiability::Stopped => eprintln!("{}", "Interrupted.".yellow()),
+		}),
+		_ => falsifying_valuation_until(&expr, stop).map(|result| match result {
+			Satisfiability::Satisfiable(valuation) => {
+				println!("{}\n{}", "not valid, falsified by:".red(), valuation)
+			},
+			Satisfiability::Unsatisfiable => println!("{}", "valid.".green()),
+			Satisfiability::Stopped => eprintln!("{}", "Interrupted.".yellow()),
+		}),
+	};
+	if let Err(e) = result {
+		eprintln!("{}", e.to_string().red());
 	}
+}
 
-	let mut formula = query.to_string();
-	formula.push(';');
+/// Prints the warnings of `solver` after the first `shown` ones, which were already printed.
+fn print_warnings(solver: &Solver, shown: &mut usize) {
+	for warning in &solver.warnings()[*shown..] {
+		eprint!("{}", "Warning: ".yellow());
+		eprintln!("{}", warning);
+	}
+	*shown = solver.warnings().len();
+}
 
-	match parse_formula(&formula) {
-		Ok(expr) => match clausifier.clausify(types::ast::Expression::Not(Box::new(expr))) {
-			Ok(goal_program) => match goal_program.get_clause(0) {
-				Some(goal_clause) => {
-					resolution::resolution::sld_resolution(
-						&clausifier.get_program(),
-						goal_clause,
-						rl,
-					);
+fn set_cmd(engine: &mut Engine, setting: Option<&str>, value: Option<&str>) {
+	let mut limits = engine.limits();
+	match (setting, value) {
+		(None, _) => {
+			let show = |limit: Option<String>| limit.unwrap_or_else(|| "off".to_string());
+			println!("depth: {}", show(limits.max_depth.map(|n| n.to_string())));
+			println!("steps: {}", show(limits.max_steps.map(|n| n.to_string())));
+			println!("timeout: {}", show(limits.timeout.map(|t| format!("{}s", t.as_secs_f64()))));
+			println!("occurs: {}", engine.occurs_check());
+			println!("cnf: {}", engine.cnf_mode());
+			return;
+		},
+		(Some(_), None) => {
+			eprintln!("{}", "Error: Missing value, like: set <setting> <value|off>".red());
+			return;
+		},
+		(Some("occurs"), Some(value)) => {
+			match value.parse() {
+				Ok(mode) => engine.set_occurs_check(mode),
+				Err(msg) => {
+					eprint!("{}", "Error: ".red());
+					eprintln!("{}", msg);
 				},
-				None => eprintln!("No clauses found in the goal program."),
-			},
-			Err(e) => eprintln!("Error clausifying query: {}", e),
+			}
+			return;
 		},
-		Err(e) => {
-			eprintln!("Parse error: {}", e);
+		(Some("cnf"), Some(value)) => {
+			match value.parse() {
+				Ok(mode) => engine.set_cnf_mode(mode),
+				Err(msg) => {
+					eprint!("{}", "Error: ".red());
+					eprintln!("{}", msg);
+				},
+			}
+			return;
 		},
+		(Some(setting), Some(value)) => {
+			let parsed = match setting {
+				"depth" => parse_setting(value, |v| v.parse().ok()).map(|n| limits.max_depth = n),
+				"steps" => parse_setting(value, |v| v.parse().ok()).map(|n| limits.max_steps = n),
+				"timeout" => parse_setting(value, |v| {
+					v.parse::<f64>().ok().and_then(|secs| Duration::try_from_secs_f64(secs).ok())
+				})
+				.map(|t| limits.timeout = t),
+				_ => {
+					eprint!("{}", "Error: ".red());
+					eprintln!(
+						"Unknown setting: '{}'. Use 'help' for the list of settings.",
+						setting
+					);
+					return;
+				},
+			};
+			if parsed.is_none() {
+				eprint!("{}", "Error: ".red());
+				eprintln!("Invalid value for {}: '{}'", setting, value);
+				return;
+			}
+		},
+	}
+	engine.set_limits(limits);
+}
+
+/// Parses a setting value, where `off` disables the setting.
+fn parse_setting<T>(value: &str, parse: impl Fn(&str) -> Option<T>) -> Option<Option<T>> {
+	if value.eq_ignore_ascii_case("off") {
+		Some(None)
+	} else {
+		parse(value).map(Some)
+	}
+}
+
+fn continue_prompt(rl: &mut Editor<(), FileHistory>) -> bool {
+	let readline = rl.readline("Continue? (Y/N) ");
+	match readline {
+		Ok(input) => input.trim().eq_ignore_ascii_case("y"),
+		Err(_) => false, // Exit if there's an error reading input
 	}
 }
@@ -0,0 +1,237 @@
+use super::mgu::{MguError, OccursCheck, Result};
+use crate::types::ast::{Proposition, Term};
+use crate::types::clause::{Clause, Literal};
+
+/// The variable bindings of a derivation, kept in a single mutable store.
+///
+/// Unification binds variables in place instead of composing substitutions, and every binding is
+/// recorded on a trail. Backtracking to a choice point only has to undo the bindings made after
+/// it, so the cost of exploring a branch is proportional to the bindings it makes, not to the size
+/// of the whole substitution.
+#[derive(Debug, Clone, Default)]
+pub struct Bindings {
+	/// The value of each variable, indexed by the number in `Term::Var`.
+	values: Vec<Option<Term>>,
+	trail: Vec<usize>,
+}
+
+impl Bindings {
+	pub fn new() -> Self {
+		Bindings::default()
+	}
+
+	/// The term bound to `var`, if any. The term may itself hold bound variables.
+	pub fn get(&self, var: usize) -> Option<&Term> {
+		self.values.get(var).and_then(Option::as_ref)
+	}
+
+	/// Binds the unbound variable `var` to `value`.
+	pub fn bind(&mut self, var: usize, value: Term) {
+		if var >= self.values.len() {
+			self.values.resize(var + 1, None);
+		}
+		self.values[var] = Some(value);
+		self.trail.push(var);
+	}
+
+	/// A position in the trail, to be passed to [`Bindings::undo_to`] when backtracking.
+	pub fn mark(&self) -> usize {
+		self.trail.len()
+	}
+
+	/// Removes every binding made since `mark` was taken.
+	pub fn undo_to(&mut self, mark: usize) {
+		while self.trail.len() > mark {
+			if let Some(var) = self.trail.pop() {
+				self.values[var] = None;
+			}
+		}
+	}
+
+	/// Follows the bindings of `term` until reaching a compound term, a number or an unbound
+	/// variable.
+	pub fn walk<'a>(&'a self, mut term: &'a Term) -> &'a Term {
+		while let Term::Var(var) = term {
+			match self.get(*var) {
+				Some(value) => term = value,
+				None => break,
+			}
+		}
+		term
+	}
+
+	/// Replaces every bound variable in `term` by its value, recursively. A variable bound to a
+	/// term containing itself, which is possible without the occurs check, is left unresolved
+	/// inside its own value.
+	pub fn resolve(&self, term: &Term) -> Term {
+		self.resolve_in(term, &mut vec![])
+	}
+
+	fn resolve_in(&self, term: &Term, expanding: &mut Vec<usize>) -> Term {
+		match term {
+			Term::Var(var) if expanding.contains(var) => term.clone(),
+			Term::Var(var) => match self.get(*var) {
+				Some(value) => {
+					expanding.push(*var);
+					let resolved = self.resolve_in(value, expanding);
+					expanding.pop();
+					resolved
+				},
+				None => term.clone(),
+			},
+			Term::FunctionApplication { name, args } => Term::FunctionApplication {
+				name: name.clone(),
+				args: args.iter().map(|arg| self.resolve_in(arg, expanding)).collect(),
+			},
+			Term::Identifier(_) | Term::Number(_) => term.clone(),
+		}
+	}
+
+	pub fn resolve_prop(&self, prop: &Proposition) -> Proposition {
+		Proposition {
+			name: prop.name.clone(),
+			terms: prop.terms.iter().map(|t| self.resolve(t)).collect(),
+		}
+	}
+
+	pub fn resolve_literal(&self, lit: &Literal) -> Literal {
+		match lit {
+			Literal::Proposition(prop) => Literal::Proposition(self.resolve_prop(prop)),
+			Literal::Not(prop) => Literal::Not(self.resolve_prop(prop)),
+			Literal::NegationAsFailure(goal) => Literal::NegationAsFailure(Clause(
+				goal.iter().map(|lit| self.resolve_literal(lit)).collect(),
+			)),
+		}
+	}
+
+	/// Unifies `t1` and `t2`, binding their variables. On failure the bindings made so far are
+	/// kept, so callers are expected to undo to a mark taken before the call.
+	pub fn unify(&mut self, t1: &Term, t2: &Term, occurs_check: OccursCheck) -> Result<()> {
+		let mut pending = vec![(t1.clone(), t2.clone())];
+		while let Some((t1, t2)) = pending.pop() {
+			let t1 = self.walk(&t1).clone();
+			let t2 = self.walk(&t2).clone();
+			match (&t1, &t2) {
+				(Term::Var(v1), Term::Var(v2)) if v1 == v2 => {},
+				(Term::Var(var), term) | (term, Term::Var(var)) => {
+					if occurs_check.is_enabled() && self.occurs(*var, term) {
+						return Err(MguError::OccursCheck(format!(
+							"{} occurs in {}",
+							Term::Var(*var),
+							self.resolve(term)
+						)));
+					}
+					self.bind(*var, term.clone());
+				},
+				(Term::Identifier(id1), Term::Identifier(id2)) if id1 == id2 => {},
+				(Term::Number(n1), Term::Number(n2)) => {
+					if n1 != n2 {
+						return Err(MguError::Clash(format!(
+							"Cannot unify numbers: {} and {}",
+							n1, n2
+						)));
+					}
+				},
+				(
+					Term::FunctionApplication { name: n1, args: a1 },
+					Term::FunctionApplication { name: n2, args: a2 },
+				) if n1 == n2 && a1.len() == a2.len() => {
+					pending.extend(a1.iter().cloned().zip(a2.iter().cloned()).rev());
+				},
+				_ => {
+					return Err(MguError::Clash(format!("Cannot unify {} and {}", t1, t2)));
+				},
+			}
+		}
+		Ok(())
+	}
+
+	/// Unifies two propositions with the same name and arity argument by argument.
+	pub fn unify_props(
+		&mut self,
+		p1: &Proposition,
+		p2: &Proposition,
+		occurs_check: OccursCheck,
+	) -> Result<()> {
+		if p1.name != p2.name || p1.terms.len() != p2.terms.len() {
+			return Err(MguError::Clash(format!("Cannot unify {} and {}", p1, p2)));
+		}
+		for (t1, t2) in p1.terms.iter().zip(&p2.terms) {
+			self.unify(t1, t2, occurs_check)?;
+		}
+		Ok(())
+	}
+
+	fn occurs(&self, var: usize, term: &Term) -> bool {
+		match self.walk(term) {
+			Term::Var(id) => *id == var,
+			Term::FunctionApplication { args, .. } => args.iter().any(|arg| self.occurs(var, arg)),
+			Term::Identifier(_) | Term::Number(_) => false,
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	const X: usize = 0;
+	const Y: usize = 1;
+
+	fn var(id: usize) -> Term {
+		Term::Var(id)
+	}
+
+	fn app(name: &str, args: Vec<Term>) -> Term {
+		Term::FunctionApplication { name: name.to_string(), args }
+	}
+
+	#[test]
+	fn test_unify_and_resolve() {
+		let mut bindings = Bindings::new();
+		let t1 = app("f", vec![var(X), app("g", vec![var(Y)])]);
+		let t2 = app("f", vec![app("g", vec![var(Y)]), app("g", vec![Term::Number(1)])]);
+
+		bindings.unify(&t1, &t2, OccursCheck::Off).unwrap();
+
+		assert_eq!(bindings.resolve(&var(X)), app("g", vec![Term::Number(1)]));
+		assert_eq!(bindings.resolve(&t1), bindings.resolve(&t2));
+	}
+
+	#[test]
+	fn test_undo_to_mark() {
+		let mut bindings = Bindings::new();
+		bindings.unify(&var(X), &Term::Number(1), OccursCheck::Off).unwrap();
+		let mark = bindings.mark();
+
+		bindings.unify(&var(Y), &var(X), OccursCheck::Off).unwrap();
+		assert_eq!(bindings.resolve(&var(Y)), Term::Number(1));
+
+		bindings.undo_to(mark);
+		assert_eq!(bindings.resolve(&var(Y)), var(Y));
+		assert_eq!(bindings.resolve(&var(X)), Term::Number(1));
+	}
+
+	#[test]
+	fn test_clash() {
+		let mut bindings = Bindings::new();
+		let t1 = app("f", vec![var(X), Term::Number(1)]);
+		let t2 = app("f", vec![var(Y), Term::Number(2)]);
+
+		assert!(matches!(bindings.unify(&t1, &t2, OccursCheck::Off), Err(MguError::Clash(_))));
+	}
+
+	#[test]
+	fn test_cyclic_binding() {
+		let cyclic = app("f", vec![var(X)]);
+
+		let mut bindings = Bindings::new();
+		assert!(matches!(
+			bindings.unify(&var(X), &cyclic, OccursCheck::On),
+			Err(MguError::OccursCheck(_))
+		));
+
+		bindings.unify(&var(X), &cyclic, OccursCheck::Off).unwrap();
+		assert_eq!(bindings.resolve(&var(X)), cyclic);
+	}
+}
@@ -2,7 +2,7 @@ use super::substitution::{
 	apply_substitution_to_equation, compose_substitutions, empty_substitution,
 };
 use crate::types::ast::{Proposition, Term};
-use std::{collections::HashMap, fmt};
+use std::{collections::HashMap, fmt, str::FromStr};
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum Unifiable {
@@ -44,7 +44,60 @@ impl fmt::Display for MguError {
 
 pub type Result<T> = std::result::Result<T, MguError>;
 
-pub fn mgu(mut equations: UnificationEquation) -> Result<Substitution> {
+/// How unification treats a variable that occurs in the term it is unified with, as in
+/// `X = f(X)`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum OccursCheck {
+	/// Bind the variable anyway, as Prolog does. Faster, but unsound: the binding is cyclic.
+	#[default]
+	Off,
+	/// Fail to unify.
+	On,
+	/// Fail to unify, and let the caller report it as an error.
+	Error,
+}
+
+impl OccursCheck {
+	pub fn is_enabled(self) -> bool {
+		self != OccursCheck::Off
+	}
+}
+
+impl fmt::Display for OccursCheck {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			OccursCheck::Off => write!(f, "off"),
+			OccursCheck::On => write!(f, "on"),
+			OccursCheck::Error => write!(f, "error"),
+		}
+	}
+}
+
+impl FromStr for OccursCheck {
+	type Err = String;
+
+	fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+		match s.to_ascii_lowercase().as_str() {
+			"off" => Ok(OccursCheck::Off),
+			"on" => Ok(OccursCheck::On),
+			"error" => Ok(OccursCheck::Error),
+			_ => Err(format!("Invalid occurs check mode: '{}'", s)),
+		}
+	}
+}
+
+/// Computes the most general unifier of `equations`, with the occurs check. See [`mgu_with`] to
+/// skip it.
+pub fn mgu(equations: UnificationEquation) -> Result<Substitution> {
+	mgu_with(equations, OccursCheck::On)
+}
+
+/// Computes the most general unifier of `equations`. Unless `occurs_check` is `Off`, binding a
+/// variable to a term containing it fails with [`MguError::OccursCheck`].
+pub fn mgu_with(
+	mut equations: UnificationEquation,
+	occurs_check: OccursCheck,
+) -> Result<Substitution> {
 	let mut sub = empty_substitution();
 
 	while let Some(pair) = equations.pop() {
@@ -52,7 +105,7 @@ pub fn mgu(mut equations: UnificationEquation) -> Result<Substitution> {
 			Ok(decomposed) => {
 				equations.extend(decomposed);
 			},
-			Err(_e) => match delete(&pair) {
+			Err(_e) => match delete(&pair, occurs_check) {
 				Ok(new_sub) => {
 					compose_substitutions(&new_sub, &mut sub);
 					apply_substitution_to_equation(&sub, &mut equations);
@@ -73,8 +126,8 @@ fn decompose(pair: &UnifiablePair) -> Result<Vec<UnifiablePair>> {
 			Unifiable::Term(Term::FunctionApplication { name: n1, args: a1 }),
 			Unifiable::Term(Term::FunctionApplication { name: n2, args: a2 }),
 		) if n1 == n2 && a1.len() == a2.len() => Ok(a1
-			.into_iter()
-			.zip(a2.into_iter())
+			.iter()
+			.zip(a2)
 			.map(|(l, r)| (Unifiable::Term(l.clone()), Unifiable::Term(r.clone())))
 			.collect()),
 		(Unifiable::Prop(p1), Unifiable::Prop(p2))
@@ -84,7 +137,7 @@ fn decompose(pair: &UnifiablePair) -> Result<Vec<UnifiablePair>> {
 				.clone()
 				.terms
 				.into_iter()
-				.zip(p2.clone().terms.into_iter())
+				.zip(p2.clone().terms)
 				.map(|(l, r)| (Unifiable::Term(l), Unifiable::Term(r)))
 				.collect())
 		},
@@ -92,66 +145,20 @@ fn decompose(pair: &UnifiablePair) -> Result<Vec<UnifiablePair>> {
 	}
 }
 
-fn delete(pair: &UnifiablePair) -> Result<Substitution> {
+fn delete(pair: &UnifiablePair, check: OccursCheck) -> Result<Substitution> {
 	match pair {
-		(Unifiable::Term(Term::Identifier(ref id)), Unifiable::Term(Term::Identifier(ref id1))) => {
-			if id == id1 {
-				return Ok(empty_substitution());
-			}
-			let mut sub = empty_substitution();
-			sub.insert(
-				Unifiable::Term(Term::Identifier(id.clone())),
-				Unifiable::Term(Term::Identifier(id1.clone())),
-			);
-			Ok(sub)
-		},
-		(
-			Unifiable::Term(Term::Identifier(ref id)),
-			Unifiable::Term(Term::FunctionApplication { name, args }),
-		) => {
-			let var = Term::Identifier(id.clone());
-			let term = Term::FunctionApplication { name: name.to_string(), args: args.to_vec() };
-			/* if occurs_check(&var, &term) {
-				return Err(MguError::OccursCheck(format!("{} occurs in {}", var, term)));
-			} */
-			let mut sub = empty_substitution();
-			sub.insert(Unifiable::Term(var), Unifiable::Term(term));
-			return Ok(sub);
-		},
-		(
-			Unifiable::Term(Term::FunctionApplication { name, args }),
-			Unifiable::Term(Term::Identifier(ref id)),
-		) => {
-			// swap case
-			let var = Term::Identifier(id.clone());
-			let term = Term::FunctionApplication { name: name.to_string(), args: args.to_vec() };
-			if occurs_check(&var, &term) {
-				return Err(MguError::OccursCheck(format!("{} occurs in {}", var, term)));
-			}
-			let mut sub = empty_substitution();
-			sub.insert(Unifiable::Term(var), Unifiable::Term(term));
-			return Ok(sub);
+		(Unifiable::Term(t1), Unifiable::Term(t2)) if is_variable(t1) && t1 == t2 => {
+			Ok(empty_substitution())
 		},
+		(Unifiable::Term(var), Unifiable::Term(term)) if is_variable(var) => bind(var, term, check),
+		// swap case
+		(Unifiable::Term(term), Unifiable::Term(var)) if is_variable(var) => bind(var, term, check),
 		(Unifiable::Term(Term::Number(n1)), Unifiable::Term(Term::Number(n2))) => {
 			if n1 == n2 {
 				return Ok(empty_substitution());
 			}
 			Err(MguError::Clash(format!("Cannot unify numbers: {} and {}", n1, n2)))
 		},
-		(Unifiable::Term(Term::Identifier(ref id)), Unifiable::Term(Term::Number(n))) => {
-			let var = Term::Identifier(id.clone());
-			let term = Term::Number(*n);
-			let mut sub = empty_substitution();
-			sub.insert(Unifiable::Term(var), Unifiable::Term(term));
-			return Ok(sub);
-		},
-		(Unifiable::Term(Term::Number(n)), Unifiable::Term(Term::Identifier(ref id))) => {
-			let var = Term::Identifier(id.clone());
-			let term = Term::Number(*n);
-			let mut sub = empty_substitution();
-			sub.insert(Unifiable::Term(var), Unifiable::Term(term));
-			return Ok(sub);
-		},
 		_ => Err(MguError::UnificationError(format!(
 			"Cannot delete terms: {:?} and {:?}",
 			pair.0, pair.1
@@ -159,9 +166,23 @@ fn delete(pair: &UnifiablePair) -> Result<Substitution> {
 	}
 }
 
+/// Variables are either named, as written in the source, or numbered, as in renamed clauses.
+fn is_variable(term: &Term) -> bool {
+	matches!(term, Term::Identifier(_) | Term::Var(_))
+}
+
+fn bind(var: &Term, term: &Term, check: OccursCheck) -> Result<Substitution> {
+	if check.is_enabled() && occurs_check(var, term) {
+		return Err(MguError::OccursCheck(format!("{} occurs in {}", var, term)));
+	}
+	let mut sub = empty_substitution();
+	sub.insert(Unifiable::Term(var.clone()), Unifiable::Term(term.clone()));
+	Ok(sub)
+}
+
 fn occurs_check(var: &Term, term: &Term) -> bool {
 	match term {
-		Term::Identifier(_) => term == var,
+		Term::Identifier(_) | Term::Var(_) => term == var,
 		Term::FunctionApplication { args, .. } => args.iter().any(|arg| occurs_check(var, arg)),
 		Term::Number(_) => false, // Numbers do not contain variables
 	}
@@ -186,7 +207,7 @@ mod tests {
 		let clause2_clone = clause2.clone();
 		let equations = vec![(clause1, clause2)];
 		let result = mgu(equations);
-		let clause1_sub = apply_substitution(&result.as_ref().unwrap(), &clause1_clone);
+		let clause1_sub = apply_substitution(result.as_ref().unwrap(), &clause1_clone);
 		assert_eq!(clause1_sub, clause2_clone);
 	}
 
@@ -219,7 +240,7 @@ mod tests {
 		let equations = vec![(clause1, clause2)];
 		let result = mgu(equations);
 		// check that x is substituted with g(Y)
-		let clause1_sub = apply_substitution(&result.as_ref().unwrap(), &clause1_clone);
+		let clause1_sub = apply_substitution(result.as_ref().unwrap(), &clause1_clone);
 		let expected = Unifiable::Term(Term::FunctionApplication {
 			name: "f".to_string(),
 			args: vec![Term::FunctionApplication {
@@ -262,4 +283,40 @@ mod tests {
 		let sub = result.unwrap();
 		assert!(sub.is_empty(), "Expected empty substitution for trivial MGU");
 	}
+
+	#[test]
+	fn test_occurs_check() {
+		let var = Unifiable::Term(Term::Identifier("X".to_string()));
+		let cyclic = Unifiable::Term(Term::FunctionApplication {
+			name: "f".to_string(),
+			args: vec![Term::Identifier("X".to_string())],
+		});
+		for equations in [vec![(var.clone(), cyclic.clone())], vec![(cyclic, var)]] {
+			assert!(matches!(mgu(equations.clone()), Err(MguError::OccursCheck(_))));
+			assert!(mgu_with(equations.clone(), OccursCheck::Off).is_ok());
+			assert!(matches!(
+				mgu_with(equations.clone(), OccursCheck::On),
+				Err(MguError::OccursCheck(_))
+			));
+			assert!(matches!(
+				mgu_with(equations, OccursCheck::Error),
+				Err(MguError::OccursCheck(_))
+			));
+		}
+	}
+
+	#[test]
+	fn test_numbered_variables() {
+		let f = |args| Unifiable::Term(Term::FunctionApplication { name: "f".to_string(), args });
+		let a = Term::FunctionApplication { name: "a".to_string(), args: vec![] };
+		let t1 = f(vec![Term::Var(0), Term::Identifier("Y".to_string())]);
+		let t2 = f(vec![a.clone(), Term::Var(0)]);
+
+		let sub = mgu(vec![(t1.clone(), t2.clone())]).unwrap();
+		assert_eq!(apply_substitution(&sub, &t1), f(vec![a.clone(), a]));
+		assert_eq!(apply_substitution(&sub, &t1), apply_substitution(&sub, &t2));
+
+		let cyclic = vec![(Unifiable::Term(Term::Var(0)), f(vec![Term::Var(0)]))];
+		assert!(matches!(mgu(cyclic), Err(MguError::OccursCheck(_))));
+	}
 }
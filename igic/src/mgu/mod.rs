@@ -1,2 +1,4 @@
+pub mod bindings;
+#[allow(clippy::module_inception)]
 pub mod mgu;
 pub mod substitution;
@@ -15,7 +15,9 @@ pub fn empty_substitution() -> Substitution {
 
 pub fn apply_substitution(sub: &Substitution, t: &Unifiable) -> Unifiable {
 	match t {
-		Unifiable::Term(Term::Identifier(_)) => sub.get(t).cloned().unwrap_or(t.clone()),
+		Unifiable::Term(Term::Identifier(_) | Term::Var(_)) => {
+			sub.get(t).cloned().unwrap_or(t.clone())
+		},
 		Unifiable::Term(Term::FunctionApplication { name, args }) => {
 			let new_args = args
 				.iter()
@@ -67,6 +69,7 @@ pub fn apply_substitution_to_clause(sub: &Substitution, clause: &mut Clause) {
 					.collect();
 				*literal = Literal::Not(Proposition { name: prop.name.clone(), terms: new_terms });
 			},
+			Literal::NegationAsFailure(goal) => apply_substitution_to_clause(sub, goal),
 		}
 	}
 }
@@ -0,0 +1,288 @@
+use std::ops::Not;
+
+/// A propositional literal: a variable, numbered from 0, or its negation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Lit {
+	pub var: usize,
+	pub positive: bool,
+}
+
+impl Lit {
+	pub fn pos(var: usize) -> Self {
+		Lit { var, positive: true }
+	}
+
+	pub fn neg(var: usize) -> Self {
+		Lit { var, positive: false }
+	}
+
+	/// The index of the literal in the watch lists.
+	fn code(self) -> usize {
+		2 * self.var + usize::from(!self.positive)
+	}
+}
+
+impl Not for Lit {
+	type Output = Lit;
+
+	fn not(self) -> Lit {
+		Lit { var: self.var, positive: !self.positive }
+	}
+}
+
+/// The outcome of [`Dpll::solve_until`], whose assignments are indexed by variable, or of a search
+/// reporting them as `A`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Satisfiability<A = Vec<bool>> {
+	/// A satisfying assignment.
+	Satisfiable(A),
+	Unsatisfiable,
+	/// The search was stopped before reaching either result.
+	Stopped,
+}
+
+impl<A> Satisfiability<A> {
+	/// The satisfying assignment, or `None` if there is none or the search was stopped.
+	pub fn into_option(self) -> Option<A> {
+		match self {
+			Satisfiability::Satisfiable(assignment) => Some(assignment),
+			_ => None,
+		}
+	}
+}
+
+/// A DPLL SAT solver for clauses over the variables `0..vars`.
+///
+/// The search assigns variables in order, false first, and propagates unit clauses with two
+/// watched literals per clause. On a conflict it backtracks to the last decision not yet flipped.
+#[derive(Debug, Default)]
+pub struct Dpll {
+	clauses: Vec<Vec<Lit>>,
+	/// The clauses watching each literal, indexed by [`Lit::code`]. A clause watches its first two
+	/// literals.
+	watches: Vec<Vec<usize>>,
+	units: Vec<Lit>,
+	/// True once an empty clause was added.
+	conflict: bool,
+	assignment: Vec<Option<bool>>,
+	trail: Vec<Lit>,
+	/// The trail length before each decision, the decision, and whether it was already flipped.
+	decisions: Vec<(usize, Lit, bool)>,
+	propagated: usize,
+}
+
+impl Dpll {
+	pub fn new() -> Self {
+		Dpll::default()
+	}
+
+	/// Adds a fresh variable and returns it.
+	pub fn new_var(&mut self) -> usize {
+		self.assignment.push(None);
+		self.watches.push(Vec::new());
+		self.watches.push(Vec::new());
+		self.assignment.len() - 1
+	}
+
+	pub fn vars(&self) -> usize {
+		self.assignment.len()
+	}
+
+	/// Adds the disjunction of `lits`, whose variables must have been created with
+	/// [`new_var`](Dpll::new_var). Tautologies are dropped.
+	pub fn add_clause(&mut self, lits: impl IntoIterator<Item = Lit>) {
+		let mut clause: Vec<Lit> = Vec::new();
+		for lit in lits {
+			if clause.contains(&!lit) {
+				return;
+			}
+			if !clause.contains(&lit) {
+				clause.push(lit);
+			}
+		}
+		match clause.len() {
+			0 => self.conflict = true,
+			1 => self.units.push(clause[0]),
+			_ => {
+				let index = self.clauses.len();
+				self.watches[clause[0].code()].push(index);
+				self.watches[clause[1].code()].push(index);
+				self.clauses.push(clause);
+			},
+		}
+	}
+
+	/// Searches for a satisfying assignment.
+	pub fn solve(&mut self) -> Option<Vec<bool>> {
+		self.solve_until(|| false).into_option()
+	}
+
+	/// Searches for a satisfying assignment, calling `stop` before each decision and giving up as
+	/// soon as it returns true.
+	pub fn solve_until(&mut self, mut stop: impl FnMut() -> bool) -> Satisfiability {
+		self.reset();
+		if self.conflict {
+			return Satisfiability::Unsatisfiable;
+		}
+		for unit in self.units.clone() {
+			match value(&self.assignment, unit) {
+				Some(false) => return Satisfiability::Unsatisfiable,
+				Some(true) => {},
+				None => self.assign(unit),
+			}
+		}
+		if !self.propagate() {
+			return Satisfiability::Unsatisfiable;
+		}
+
+		loop {
+			let Some(var) = self.assignment.iter().position(Option::is_none) else {
+				let assignment = self.assignment.iter().map(|v| v.unwrap_or(false)).collect();
+				return Satisfiability::Satisfiable(assignment);
+			};
+			if stop() {
+				return Satisfiability::Stopped;
+			}
+			self.decisions.push((self.trail.len(), Lit::neg(var), false));
+			self.assign(Lit::neg(var));
+			while !self.propagate() {
+				if !self.backtrack() {
+					return Satisfiability::Unsatisfiable;
+				}
+			}
+		}
+	}
+
+	fn reset(&mut self) {
+		for lit in self.trail.drain(..) {
+			self.assignment[lit.var] = None;
+		}
+		self.decisions.clear();
+		self.propagated = 0;
+	}
+
+	fn assign(&mut self, lit: Lit) {
+		self.assignment[lit.var] = Some(lit.positive);
+		self.trail.push(lit);
+	}
+
+	/// Undoes the assignments since the last decision not yet flipped, and flips it. Returns false
+	/// if there is no such decision.
+	fn backtrack(&mut self) -> bool {
+		while let Some((mark, decision, flipped)) = self.decisions.pop() {
+			for lit in self.trail.drain(mark..) {
+				self.assignment[lit.var] = None;
+			}
+			self.propagated = mark;
+			if !flipped {
+				self.decisions.push((mark, !decision, true));
+				self.assign(!decision);
+				return true;
+			}
+		}
+		false
+	}
+
+	/// Assigns the literals implied by unit clauses until none is left. Returns false on a
+	/// conflict.
+	fn propagate(&mut self) -> bool {
+		while self.propagated < self.trail.len() {
+			let falsified = !self.trail[self.propagated];
+			self.propagated += 1;
+			let mut watching = std::mem::take(&mut self.watches[falsified.code()]);
+			let mut i = 0;
+			while i < watching.len() {
+				let clause = &mut self.clauses[watching[i]];
+				if clause[0] == falsified {
+					clause.swap(0, 1);
+				}
+				if value(&self.assignment, clause[0]) == Some(true) {
+					i += 1;
+					continue;
+				}
+				let replacement =
+					(2..clause.len()).find(|&k| value(&self.assignment, clause[k]) != Some(false));
+				if let Some(k) = replacement {
+					clause.swap(1, k);
+					self.watches[clause[1].code()].push(watching.swap_remove(i));
+					continue;
+				}
+				match value(&self.assignment, clause[0]) {
+					Some(false) => {
+						self.watches[falsified.code()] = watching;
+						return false;
+					},
+					_ => {
+						let unit = clause[0];
+						self.assign(unit);
+						i += 1;
+					},
+				}
+			}
+			self.watches[falsified.code()] = watching;
+		}
+		true
+	}
+}
+
+fn value(assignment: &[Option<bool>], lit: Lit) -> Option<bool> {
+	assignment[lit.var].map(|v| v == lit.positive)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn satisfies(assignment: &[bool], clauses: &[Vec<Lit>]) -> bool {
+		clauses.iter().all(|c| c.iter().any(|lit| assignment[lit.var] == lit.positive))
+	}
+
+	#[test]
+	fn test_satisfiable() {
+		let mut solver = Dpll::new();
+		let (a, b, c) = (solver.new_var(), solver.new_var(), solver.new_var());
+		let clauses = vec![
+			vec![Lit::pos(a), Lit::pos(b)],
+			vec![Lit::neg(a), Lit::pos(c)],
+			vec![Lit::neg(b), Lit::pos(c)],
+			vec![Lit::neg(c), Lit::neg(a)],
+		];
+		for clause in &clauses {
+			solver.add_clause(clause.clone());
+		}
+		let assignment = solver.solve().unwrap();
+		assert!(satisfies(&assignment, &clauses));
+		assert_eq!(assignment, vec![false, true, true]);
+	}
+
+	#[test]
+	fn test_pigeonhole_is_unsatisfiable() {
+		// Three pigeons in two holes: var(p, h) means pigeon p is in hole h.
+		let mut solver = Dpll::new();
+		let vars: Vec<Vec<usize>> =
+			(0..3).map(|_| (0..2).map(|_| solver.new_var()).collect()).collect();
+		for pigeon in &vars {
+			solver.add_clause(pigeon.iter().map(|&v| Lit::pos(v)));
+		}
+		for hole in 0..2 {
+			for (p, pigeon) in vars.iter().enumerate() {
+				for other in &vars[p + 1..] {
+					solver.add_clause([Lit::neg(pigeon[hole]), Lit::neg(other[hole])]);
+				}
+			}
+		}
+		assert_eq!(solver.solve(), None);
+	}
+
+	#[test]
+	fn test_empty_clause_and_stop() {
+		let mut solver = Dpll::new();
+		let a = solver.new_var();
+		solver.add_clause([Lit::pos(a), Lit::neg(a)]);
+		assert_eq!(solver.solve_until(|| true), Satisfiability::Stopped);
+		assert_eq!(solver.solve(), Some(vec![false]));
+
+		solver.add_clause([]);
+		assert_eq!(solver.solve(), None);
+	}
+}
@@ -0,0 +1,478 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Instant;
+
+use crate::clauses::cnf::DEFINITION_PREFIX;
+use crate::models::dpll::{Dpll, Lit, Satisfiability};
+use crate::resolution::limits::{LimitExceeded, Limits};
+use crate::types::ast::{Proposition, Term};
+use crate::types::clause::{Clause, Literal};
+use crate::types::{GicError, Result};
+
+/// The table of a function symbol: its value on each tuple of arguments.
+pub type FunctionTable = Vec<(Vec<usize>, usize)>;
+
+/// A finite interpretation of a clause set, over the domain `{0, ..., size - 1}`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Model {
+	pub size: usize,
+	/// The value of each function symbol, by name and arity, on each tuple of arguments.
+	pub functions: BTreeMap<(String, usize), FunctionTable>,
+	/// The tuples of arguments on which each predicate holds, by name and arity.
+	pub predicates: BTreeMap<(String, usize), Vec<Vec<usize>>>,
+}
+
+impl fmt::Display for Model {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		let domain: Vec<String> = (0..self.size).map(|e| e.to_string()).collect();
+		write!(f, "domain: {{{}}}", domain.join(", "))?;
+		for ((name, _), table) in &self.functions {
+			let entries: Vec<String> = table
+				.iter()
+				.map(|(args, value)| format!("{}{} = {}", name, tuple(args, true), value))
+				.collect();
+			write!(f, "\n{}", entries.join(", "))?;
+		}
+		for ((name, arity), holds) in &self.predicates {
+			if *arity == 0 {
+				write!(f, "\n{} = {}", name, !holds.is_empty())?;
+			} else {
+				let tuples: Vec<String> = holds.iter().map(|args| tuple(args, false)).collect();
+				write!(f, "\n{} = {{{}}}", name, tuples.join(", "))?;
+			}
+		}
+		Ok(())
+	}
+}
+
+/// Writes the arguments `args` in parentheses, leaving them out for a single argument unless
+/// `call` is set, and for no argument.
+fn tuple(args: &[usize], call: bool) -> String {
+	let args: Vec<String> = args.iter().map(|a| a.to_string()).collect();
+	match args.len() {
+		0 => String::new(),
+		1 if !call => args[0].clone(),
+		_ => format!("({})", args.join(", ")),
+	}
+}
+
+/// The outcome of a model search.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ModelResult {
+	Found(Model),
+	/// No model exists with a domain of at most this size.
+	NotFound(usize),
+	/// The search was stopped by a limit before reaching either result.
+	Unknown(LimitExceeded),
+	/// The search was stopped by the interrupt flag.
+	Interrupted,
+}
+
+impl fmt::Display for ModelResult {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			ModelResult::Found(model) => write!(f, "{}", model),
+			ModelResult::NotFound(size) => write!(f, "no model with up to {} elements", size),
+			ModelResult::Unknown(_) => write!(f, "unknown (limit reached)"),
+			ModelResult::Interrupted => write!(f, "unknown (interrupted)"),
+		}
+	}
+}
+
+/// A literal of a flat clause, whose arguments are all variables.
+#[derive(Debug, Clone)]
+enum FlatLiteral {
+	/// `P(x1, ..., xn)` or its negation.
+	Predicate { positive: bool, name: String, args: Vec<usize> },
+	/// `f(x1, ..., xn) = y` or its negation.
+	Function { positive: bool, name: String, args: Vec<usize>, value: usize },
+	/// `x = y` or its negation.
+	Equal { positive: bool, left: usize, right: usize },
+}
+
+/// A clause flattened so that each function application is named by a variable.
+#[derive(Debug, Clone)]
+struct FlatClause {
+	literals: Vec<FlatLiteral>,
+	vars: usize,
+}
+
+/// Flattens `clause`, whose variables are numbered from 0 to `vars - 1`. Each function application
+/// `f(t1, ..., tn)` is replaced by a fresh variable `y`, and the literal `¬(f(x1, ..., xn) = y)` is
+/// added for the variables `xi` of its flattened arguments. The same application is always
+/// replaced by the same variable.
+fn flatten(clause: &Clause, vars: usize) -> FlatClause {
+	struct Flattener {
+		literals: Vec<FlatLiteral>,
+		names: HashMap<(String, Vec<usize>), usize>,
+		vars: usize,
+	}
+
+	impl Flattener {
+		fn application(term: &Term) -> Option<(String, &[Term])> {
+			match term {
+				Term::FunctionApplication { name, args } => Some((name.clone(), args)),
+				// Numbers are uninterpreted constants.
+				Term::Number(n) => Some((n.to_string(), &[])),
+				Term::Var(_) | Term::Identifier(_) => None,
+			}
+		}
+
+		fn args(&mut self, args: &[Term]) -> Vec<usize> {
+			args.iter().map(|arg| self.term(arg)).collect()
+		}
+
+		fn term(&mut self, term: &Term) -> usize {
+			let Some((name, args)) = Flattener::application(term) else {
+				let Term::Var(id) = term else {
+					unreachable!("clause variables are numbered before flattening")
+				};
+				return *id;
+			};
+			let args = self.args(args);
+			if let Some(&var) = self.names.get(&(name.clone(), args.clone())) {
+				return var;
+			}
+			let value = self.vars;
+			self.vars += 1;
+			self.names.insert((name.clone(), args.clone()), value);
+			self.literals.push(FlatLiteral::Function { positive: false, name, args, value });
+			value
+		}
+
+		fn literal(&mut self, positive: bool, prop: &Proposition) -> FlatLiteral {
+			let Some((left, right)) = prop.as_equation() else {
+				let args = self.args(&prop.terms);
+				return FlatLiteral::Predicate { positive, name: prop.name.clone(), args };
+			};
+			// An equation with an application on one side is kept as a function literal.
+			let sides = match (Flattener::application(left), Flattener::application(right)) {
+				(Some(application), _) => Some((application, right)),
+				(None, Some(application)) => Some((application, left)),
+				(None, None) => None,
+			};
+			match sides {
+				Some(((name, args), other)) => {
+					let args = self.args(args);
+					let value = self.term(other);
+					FlatLiteral::Function { positive, name, args, value }
+				},
+				None => {
+					let (left, right) = (self.term(left), self.term(right));
+					FlatLiteral::Equal { positive, left, right }
+				},
+			}
+		}
+	}
+
+	let mut flattener = Flattener { literals: Vec::new(), names: HashMap::new(), vars };
+	for lit in clause.iter() {
+		let flat = match lit {
+			Literal::Proposition(prop) => flattener.literal(true, prop),
+			Literal::Not(prop) => flattener.literal(false, prop),
+			Literal::NegationAsFailure(_) => {
+				unreachable!("negation as failure is rejected on input")
+			},
+		};
+		flattener.literals.push(flat);
+	}
+	FlatClause { literals: flattener.literals, vars: flattener.vars }
+}
+
+/// The propositional encoding of the clauses for one domain size.
+struct Encoding {
+	solver: Dpll,
+	/// The variable of each ground atom `P(d1, ..., dn)`.
+	predicates: HashMap<(String, Vec<usize>), usize>,
+	/// The variable of each ground equation `f(d1, ..., dn) = e`.
+	functions: HashMap<(String, Vec<usize>, usize), usize>,
+}
+
+impl Encoding {
+	fn predicate(&mut self, name: &str, args: Vec<usize>) -> usize {
+		let solver = &mut self.solver;
+		*self
+			.predicates
+			.entry((name.to_string(), args))
+			.or_insert_with(|| solver.new_var())
+	}
+}
+
+/// Every tuple of `arity` elements of the domain `{0, ..., size - 1}`, in lexicographic order.
+fn tuples(size: usize, arity: usize) -> Vec<Vec<usize>> {
+	let mut tuples = vec![vec![]];
+	for _ in 0..arity {
+		tuples = tuples
+			.into_iter()
+			.flat_map(|t| {
+				(0..size).map(move |e| {
+					let mut t = t.clone();
+					t.push(e);
+					t
+				})
+			})
+			.collect();
+	}
+	tuples
+}
+
+/// A finite model finder in the style of MACE.
+///
+/// For each domain size `n` from 1 up, the clauses are flattened so that every argument is a
+/// variable, and instantiated with every assignment of their variables to `{0, ..., n - 1}`. The
+/// ground atoms become propositional variables, and each function symbol gets exactly one value on
+/// each tuple of arguments. A [DPLL solver](Dpll) then looks for a satisfying assignment, which is
+/// a model of size `n`. The equality predicate `=` is interpreted as the identity on the domain.
+///
+/// Constants are numbered in order of appearance, and the `i`-th constant is only given values up
+/// to `i`, which prunes models that only differ by a permutation of the domain.
+pub struct ModelFinder {
+	clauses: Vec<FlatClause>,
+	functions: Vec<(String, usize)>,
+	predicates: Vec<(String, usize)>,
+	limits: Limits,
+	interrupt: Option<Arc<AtomicBool>>,
+}
+
+impl ModelFinder {
+	pub fn new<'a>(clauses: impl IntoIterator<Item = &'a Clause>) -> Result<Self> {
+		let mut finder = ModelFinder {
+			clauses: Vec::new(),
+			functions: Vec::new(),
+			predicates: Vec::new(),
+			limits: Limits::unbounded(),
+			interrupt: None,
+		};
+		for clause in clauses {
+			if clause.iter().any(|lit| matches!(lit, Literal::NegationAsFailure(_))) {
+				return Err(GicError::ResolutionError(format!(
+					"Negation as failure cannot be used in a model search: {}",
+					clause
+				)));
+			}
+			let (numbered, names) = clause.number_vars(0);
+			let flat = flatten(&numbered, names.len());
+			for lit in &flat.literals {
+				let (symbols, symbol) = match lit {
+					FlatLiteral::Predicate { name, args, .. } => {
+						(&mut finder.predicates, (name.clone(), args.len()))
+					},
+					FlatLiteral::Function { name, args, .. } => {
+						(&mut finder.functions, (name.clone(), args.len()))
+					},
+					FlatLiteral::Equal { .. } => continue,
+				};
+				if !symbols.contains(&symbol) {
+					symbols.push(symbol);
+				}
+			}
+			finder.clauses.push(flat);
+		}
+		Ok(finder)
+	}
+
+	/// Bounds the search with `limits`: `max_steps` counts the decisions of the SAT solver over all
+	/// the domain sizes. The depth limit does not apply. The time limit counts from the start of
+	/// [`find`](ModelFinder::find).
+	pub fn with_limits(mut self, limits: Limits) -> Self {
+		self.limits = limits;
+		self
+	}
+
+	/// Stops the search as soon as `flag` is set.
+	pub fn with_interrupt(mut self, flag: Arc<AtomicBool>) -> Self {
+		self.interrupt = Some(flag);
+		self
+	}
+
+	/// Searches for a model with a domain of `1` to `max_size` elements, smallest first.
+	pub fn find(&self, max_size: usize) -> ModelResult {
+		let started = Instant::now();
+		let mut steps = 0;
+		let mut stopped = None;
+		for size in 1..=max_size {
+			let mut encoding = self.encode(size);
+			let outcome = encoding.solver.solve_until(|| {
+				steps += 1;
+				stopped = self.stop(started, steps);
+				stopped.is_some()
+			});
+			match outcome {
+				Satisfiability::Satisfiable(assignment) => {
+					return ModelResult::Found(self.model(size, &encoding, &assignment));
+				},
+				Satisfiability::Unsatisfiable => {},
+				Satisfiability::Stopped => break,
+			}
+			if let Some(result) = self.stop(started, steps) {
+				stopped = Some(result);
+				break;
+			}
+		}
+		stopped.unwrap_or(ModelResult::NotFound(max_size))
+	}
+
+	fn stop(&self, started: Instant, steps: usize) -> Option<ModelResult> {
+		if self.interrupt.as_ref().is_some_and(|flag| flag.load(Ordering::Relaxed)) {
+			return Some(ModelResult::Interrupted);
+		}
+		if let Some(max_steps) = self.limits.max_steps {
+			if steps > max_steps {
+				return Some(ModelResult::Unknown(LimitExceeded::Steps(max_steps)));
+			}
+		}
+		if let Some(timeout) = self.limits.timeout {
+			if started.elapsed() > timeout {
+				return Some(ModelResult::Unknown(LimitExceeded::Time(timeout)));
+			}
+		}
+		None
+	}
+
+	fn encode(&self, size: usize) -> Encoding {
+		let mut encoding =
+			Encoding { solver: Dpll::new(), predicates: HashMap::new(), functions: HashMap::new() };
+
+		let mut constants = 0;
+		for (name, arity) in &self.functions {
+			for args in tuples(size, *arity) {
+				let values: Vec<usize> = (0..size).map(|_| encoding.solver.new_var()).collect();
+				encoding.solver.add_clause(values.iter().map(|&v| Lit::pos(v)));
+				for (i, &v) in values.iter().enumerate() {
+					for &w in &values[i + 1..] {
+						encoding.solver.add_clause([Lit::neg(v), Lit::neg(w)]);
+					}
+				}
+				if *arity == 0 {
+					for &v in values.iter().skip(constants + 1) {
+						encoding.solver.add_clause([Lit::neg(v)]);
+					}
+					constants += 1;
+				}
+				for (value, v) in values.into_iter().enumerate() {
+					encoding.functions.insert((name.clone(), args.clone(), value), v);
+				}
+			}
+		}
+
+		for clause in &self.clauses {
+			'instances: for assignment in tuples(size, clause.vars) {
+				let mut ground = Vec::new();
+				for lit in &clause.literals {
+					let lit = match lit {
+						FlatLiteral::Predicate { positive, name, args } => {
+							let args = args.iter().map(|&x| assignment[x]).collect();
+							Lit { var: encoding.predicate(name, args), positive: *positive }
+						},
+						FlatLiteral::Function { positive, name, args, value } => {
+							let args = args.iter().map(|&x| assignment[x]).collect();
+							let key = (name.clone(), args, assignment[*value]);
+							Lit { var: encoding.functions[&key], positive: *positive }
+						},
+						FlatLiteral::Equal { positive, left, right } => {
+							if (assignment[*left] == assignment[*right]) == *positive {
+								continue 'instances;
+							}
+							continue;
+						},
+					};
+					ground.push(lit);
+				}
+				encoding.solver.add_clause(ground);
+			}
+		}
+		encoding
+	}
+
+	fn model(&self, size: usize, encoding: &Encoding, assignment: &[bool]) -> Model {
+		let mut model = Model { size, functions: BTreeMap::new(), predicates: BTreeMap::new() };
+		for (name, arity) in &self.functions {
+			let table = tuples(size, *arity)
+				.into_iter()
+				.map(|args| {
+					let value = (0..size)
+						.find(|&e| assignment[encoding.functions[&(name.clone(), args.clone(), e)]])
+						.unwrap_or(0);
+					(args, value)
+				})
+				.collect();
+			model.functions.insert((name.clone(), *arity), table);
+		}
+		// Definition predicates are not part of the user's vocabulary.
+		let predicates =
+			self.predicates.iter().filter(|(name, _)| !name.starts_with(DEFINITION_PREFIX));
+		for (name, arity) in predicates {
+			let holds = tuples(size, *arity)
+				.into_iter()
+				.filter(|args| {
+					encoding
+						.predicates
+						.get(&(name.clone(), args.clone()))
+						.is_some_and(|&v| assignment[v])
+				})
+				.collect();
+			model.predicates.insert((name.clone(), *arity), holds);
+		}
+		model
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::resolution::testing::clauses;
+
+	fn finder(source: &str) -> ModelFinder {
+		ModelFinder::new(&clauses(source)).unwrap()
+	}
+
+	#[test]
+	fn test_smallest_model() {
+		let result = finder("P(a). not P(b). forall X. (P(X) impl Q(f(X))).").find(4);
+		let ModelResult::Found(model) = result else {
+			panic!("expected a model, got {:?}", result);
+		};
+		assert_eq!(model.size, 2);
+		assert_eq!(model.to_string().lines().next(), Some("domain: {0, 1}"));
+		assert_eq!(model.functions[&("a".to_string(), 0)], vec![(vec![], 0)]);
+		assert_eq!(model.functions[&("b".to_string(), 0)], vec![(vec![], 1)]);
+		assert_eq!(model.predicates[&("P".to_string(), 1)], vec![vec![0]]);
+		let f_a = model.functions[&("f".to_string(), 1)][0].1;
+		assert!(model.predicates[&("Q".to_string(), 1)].contains(&vec![f_a]));
+	}
+
+	#[test]
+	fn test_equality() {
+		// An injective function that is not surjective needs an infinite domain.
+		let injective = "forall X. forall Y. (f(X) = f(Y) impl X = Y). \
+			forall X. (not f(X) = c).";
+		assert_eq!(finder(injective).find(3), ModelResult::NotFound(3));
+
+		let result = finder("not a = b. not b = c. not a = c.").find(5);
+		assert!(matches!(result, ModelResult::Found(Model { size: 3, .. })));
+	}
+
+	#[test]
+	fn test_limit() {
+		let limits = Limits { max_steps: Some(0), ..Limits::unbounded() };
+		let result = finder("P(a) or Q(a).").with_limits(limits).find(2);
+		assert_eq!(result, ModelResult::Unknown(LimitExceeded::Steps(0)));
+	}
+
+	#[test]
+	fn test_counter_example() {
+		let program = "P(a) or Q(a). forall X. (P(X) impl R(X)). Q(b).";
+		let ModelResult::Found(model) = finder(program).find(3) else {
+			panic!("expected a model of the program");
+		};
+		assert_eq!(model.size, 1);
+
+		// R(a) does not follow: in the counter-example Q(a) holds but P(a) and R(a) do not.
+		let result = finder(&format!("{} not R(a).", program)).find(3);
+		assert_eq!(result.to_string(), "domain: {0}\na = 0\nb = 0\nP = {}\nQ = {0}\nR = {}");
+		let result = finder(&format!("{} not (P(a) or Q(a)).", program)).find(3);
+		assert_eq!(result, ModelResult::NotFound(3));
+	}
+}
@@ -0,0 +1,3 @@
+pub mod dpll;
+pub mod finder;
+pub mod propositional;
@@ -0,0 +1,267 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::clauses::cnf::{Clausifier, CnfMode};
+use crate::models::dpll::{Dpll, Lit, Satisfiability};
+use crate::parser::parse_formula;
+use crate::types::ast::{Expression, Proposition};
+use crate::types::clause::Literal;
+use crate::types::{GicError, Result};
+
+/// The largest number of atoms for which [`truth_table`] builds a table, of `2^n` rows.
+pub const MAX_TRUTH_TABLE_ATOMS: usize = 12;
+
+/// A truth value for each atom of a formula, in order of first appearance.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Valuation(pub Vec<(String, bool)>);
+
+impl fmt::Display for Valuation {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		let values: Vec<String> =
+			self.0.iter().map(|(atom, value)| format!("{} = {}", atom, value)).collect();
+		write!(f, "{}", values.join(", "))
+	}
+}
+
+/// The value of a formula under every valuation of its atoms.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TruthTable {
+	pub atoms: Vec<String>,
+	pub formula: String,
+	/// The values of the atoms and of the formula, with the atoms true first.
+	pub rows: Vec<(Vec<bool>, bool)>,
+}
+
+impl fmt::Display for TruthTable {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		let headers: Vec<&str> =
+			self.atoms.iter().map(String::as_str).chain([self.formula.as_str()]).collect();
+		let widths: Vec<usize> = headers.iter().map(|h| h.chars().count()).collect();
+		let line = |cells: Vec<&str>| -> String {
+			let cells: Vec<String> =
+				cells.iter().zip(&widths).map(|(c, w)| format!("{:<w$}", c, w = w)).collect();
+			cells.join(" | ").trim_end().to_string()
+		};
+		write!(f, "{}", line(headers.clone()))?;
+		for (values, result) in &self.rows {
+			let cells = values.iter().chain([result]).map(|&v| if v { "T" } else { "F" }).collect();
+			write!(f, "\n{}", line(cells))?;
+		}
+		Ok(())
+	}
+}
+
+/// Parses `formula` and checks that it is propositional, see [`atoms`].
+pub fn parse_propositional(formula: &str) -> Result<Expression> {
+	let formula = formula.trim();
+	if formula.is_empty() {
+		return Err(GicError::ParseError("Formula cannot be empty.".to_string()));
+	}
+	let expr = parse_formula(&format!("{};", formula))?;
+	atoms(&expr)?;
+	Ok(expr)
+}
+
+/// The atoms of a propositional formula, in order of first appearance. A formula is propositional
+/// if it has no quantifier, no negation as failure and only predicates without arguments.
+pub fn atoms(expr: &Expression) -> Result<Vec<String>> {
+	fn collect(expr: &Expression, atoms: &mut Vec<String>) -> Result<()> {
+		match expr {
+			Expression::Proposition(Proposition { name, terms }) if terms.is_empty() => {
+				if !atoms.contains(name) {
+					atoms.push(name.clone());
+				}
+				Ok(())
+			},
+			Expression::Bottom | Expression::Top => Ok(()),
+			Expression::And(a, b)
+			| Expression::Or(a, b)
+			| Expression::Xor(a, b)
+			| Expression::Implies(a, b)
+			| Expression::ImpliedBy(a, b)
+			| Expression::Iff(a, b) => {
+				collect(a, atoms)?;
+				collect(b, atoms)
+			},
+			Expression::Not(a) => collect(a, atoms),
+			_ => Err(GicError::SemanticError(format!(
+				"Not a propositional formula, which only has predicates without arguments and \
+				 connectives: {}",
+				expr
+			))),
+		}
+	}
+	let mut atoms = Vec::new();
+	collect(expr, &mut atoms)?;
+	Ok(atoms)
+}
+
+/// The truth value of a propositional formula, given the value of each of its atoms.
+pub fn evaluate(expr: &Expression, value: &dyn Fn(&str) -> bool) -> bool {
+	match expr {
+		Expression::Proposition(prop) => value(&prop.name),
+		Expression::Bottom => false,
+		Expression::Top => true,
+		Expression::And(a, b) => evaluate(a, value) && evaluate(b, value),
+		Expression::Or(a, b) => evaluate(a, value) || evaluate(b, value),
+		Expression::Xor(a, b) => evaluate(a, value) != evaluate(b, value),
+		Expression::Implies(a, b) => !evaluate(a, value) || evaluate(b, value),
+		Expression::ImpliedBy(a, b) => evaluate(a, value) || !evaluate(b, value),
+		Expression::Iff(a, b) => evaluate(a, value) == evaluate(b, value),
+		Expression::Not(a) => !evaluate(a, value),
+		_ => unreachable!("the formula is checked to be propositional"),
+	}
+}
+
+/// The truth table of a propositional formula with at most [`MAX_TRUTH_TABLE_ATOMS`] atoms.
+pub fn truth_table(expr: &Expression) -> Result<TruthTable> {
+	let atoms = atoms(expr)?;
+	if atoms.len() > MAX_TRUTH_TABLE_ATOMS {
+		return Err(GicError::SemanticError(format!(
+			"The formula has {} atoms, too many for a truth table. The limit is {}.",
+			atoms.len(),
+			MAX_TRUTH_TABLE_ATOMS
+		)));
+	}
+	let rows = (0..1usize << atoms.len())
+		.map(|row| {
+			// The first atom changes slowest, and true comes before false.
+			let values: Vec<bool> =
+				(0..atoms.len()).map(|i| row >> (atoms.len() - 1 - i) & 1 == 0).collect();
+			let result =
+				evaluate(expr, &|atom| values[atoms.iter().position(|a| a == atom).unwrap()]);
+			(values, result)
+		})
+		.collect();
+	Ok(TruthTable { atoms, formula: expr.to_string(), rows })
+}
+
+/// A valuation that makes a propositional formula true, or `None` if it is unsatisfiable.
+pub fn satisfying_valuation(expr: &Expression) -> Result<Option<Valuation>> {
+	satisfying_valuation_until(expr, || false).map(Satisfiability::into_option)
+}
+
+/// Searches for a valuation that makes a propositional formula true, giving up as soon as `stop`
+/// returns true. The formula is converted to [definitional CNF](CnfMode::Definitional), which
+/// names subformulas instead of distributing disjunctions over conjunctions, and given to the
+/// [DPLL solver](Dpll). The atoms naming subformulas are left out of the valuation.
+pub fn satisfying_valuation_until(
+	expr: &Expression,
+	stop: impl FnMut() -> bool,
+) -> Result<Satisfiability<Valuation>> {
+	let atoms = atoms(expr)?;
+	let mut solver = Dpll::new();
+	let mut vars: HashMap<String, usize> =
+		atoms.iter().map(|atom| (atom.clone(), solver.new_var())).collect();
+
+	let clauses = Clausifier::new().with_mode(CnfMode::Definitional).clausify(expr.clone())?;
+	for clause in clauses {
+		let mut var =
+			|p: &Proposition| *vars.entry(p.name.clone()).or_insert_with(|| solver.new_var());
+		let lits: Vec<Lit> = clause
+			.iter()
+			.map(|lit| match lit {
+				Literal::Proposition(p) => Lit::pos(var(p)),
+				Literal::Not(p) => Lit::neg(var(p)),
+				Literal::NegationAsFailure(_) => unreachable!("the formula is propositional"),
+			})
+			.collect();
+		solver.add_clause(lits);
+	}
+	Ok(match solver.solve_until(stop) {
+		Satisfiability::Satisfiable(assignment) => Satisfiability::Satisfiable(Valuation(
+			atoms.iter().map(|atom| (atom.clone(), assignment[vars[atom]])).collect(),
+		)),
+		Satisfiability::Unsatisfiable => Satisfiability::Unsatisfiable,
+		Satisfiability::Stopped => Satisfiability::Stopped,
+	})
+}
+
+/// A valuation that makes a propositional formula false, or `None` if it is valid.
+pub fn falsifying_valuation(expr: &Expression) -> Result<Option<Valuation>> {
+	falsifying_valuation_until(expr, || false).map(Satisfiability::into_option)
+}
+
+/// Searches for a valuation that makes a propositional formula false, giving up as soon as `stop`
+/// returns true. The result is the satisfiability of the negation of the formula.
+pub fn falsifying_valuation_until(
+	expr: &Expression,
+	stop: impl FnMut() -> bool,
+) -> Result<Satisfiability<Valuation>> {
+	satisfying_valuation_until(&Expression::Not(Box::new(expr.clone())), stop)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn test_truth_table() {
+		let table = truth_table(&parse_propositional("P impl Q").unwrap()).unwrap();
+		assert_eq!(
+			table.to_string(),
+			"P | Q | (P => Q)\nT | T | T\nT | F | F\nF | T | T\nF | F | T"
+		);
+		assert!(parse_propositional("P(a) or Q").is_err());
+		assert!(parse_propositional("forall X. P").is_err());
+	}
+
+	#[test]
+	fn test_satisfiability_and_validity() {
+		let expr = parse_propositional("(P or Q) and (not P)").unwrap();
+		let valuation = satisfying_valuation(&expr).unwrap().unwrap();
+		assert_eq!(valuation.to_string(), "P = false, Q = true");
+		assert_eq!(
+			satisfying_valuation(&parse_propositional("P and (not P)").unwrap()).unwrap(),
+			None
+		);
+
+		let excluded_middle = parse_propositional("P or (not P)").unwrap();
+		assert_eq!(falsifying_valuation(&excluded_middle).unwrap(), None);
+		let peirce = parse_propositional("((P impl Q) impl P) impl P").unwrap();
+		assert_eq!(falsifying_valuation(&peirce).unwrap(), None);
+		let counter = falsifying_valuation(&parse_propositional("P impl Q").unwrap()).unwrap();
+		assert_eq!(counter.unwrap().to_string(), "P = true, Q = false");
+		let stopped = satisfying_valuation_until(&parse_propositional("P or Q").unwrap(), || true);
+		assert_eq!(stopped.unwrap(), Satisfiability::Stopped);
+
+		// Ex falso quodlibet.
+		assert_eq!(
+			falsifying_valuation(&parse_propositional("bottom impl P").unwrap()).unwrap(),
+			None
+		);
+		assert_eq!(satisfying_valuation(&parse_propositional("bottom").unwrap()).unwrap(), None);
+	}
+
+	#[test]
+	fn test_iff_xor_and_top() {
+		let valid = |f: &str| falsifying_valuation(&parse_propositional(f).unwrap()).unwrap();
+		assert_eq!(valid("(P iff Q) iff ((P impl Q) and (Q impl P))"), None);
+		assert_eq!(valid("(P xor Q) <=> not (P ⇔ Q)"), None);
+		assert_eq!(valid("(P if Q) iff (Q impl P)"), None);
+		assert_eq!(valid("top"), None);
+		assert_eq!(valid("P or top"), None);
+		assert_eq!(valid("P xor P").unwrap().to_string(), "P = false");
+
+		let table = truth_table(&parse_propositional("P xor Q").unwrap()).unwrap();
+		let values: Vec<bool> = table.rows.iter().map(|(_, value)| *value).collect();
+		assert_eq!(values, vec![false, true, true, false]);
+	}
+
+	#[test]
+	fn test_satisfiability_of_large_formulas() {
+		// Distributing this disjunction of 40 conjunctions would give 3^40 clauses.
+		let conjunctions: Vec<String> =
+			(1..=40).map(|i| format!("(P{} and Q{} and (not R))", i, i)).collect();
+		let disjunction = conjunctions.join(" or ");
+
+		let formula = parse_propositional(&format!("({}) and R", disjunction)).unwrap();
+		assert_eq!(satisfying_valuation(&formula).unwrap(), None);
+
+		let formula = parse_propositional(&format!("({}) and (not P1)", disjunction)).unwrap();
+		let valuation = satisfying_valuation(&formula).unwrap().unwrap();
+		assert_eq!(valuation.0.len(), 81);
+		let value = |atom: &str| valuation.0.iter().any(|(a, value)| a == atom && *value);
+		assert!(evaluate(&formula, &value));
+	}
+}
@@ -3,7 +3,7 @@ use pest::Parser;
 use pest_derive::Parser;
 use pratt::{Affix, Associativity, PrattParser, Precedence};
 
-use crate::types::ast::{Expression, Proposition, Term};
+use crate::types::ast::{Expression, Proposition, Statement, Term, EQUALITY};
 use crate::types::{GicError, Result};
 
 #[derive(Parser)]
@@ -27,16 +27,33 @@ pub fn parse_formula(input: &str) -> Result<Expression> {
 		.map_err(|e| GicError::ParseError(format!("Pratt parser error: {:?}", e)))
 }
 
+/// Parses the formulas of a .gic file. Directives are skipped, see [`parse_gic_program`].
 pub fn parse_gic_file(input: &str) -> Result<Vec<Expression>> {
+	Ok(parse_gic_program(input)?
+		.into_iter()
+		.filter_map(|statement| match statement {
+			Statement::Formula(expr) => Some(expr),
+			Statement::Table(_) => None,
+		})
+		.collect())
+}
+
+/// Parses a .gic file into its formulas and directives, in order.
+pub fn parse_gic_program(input: &str) -> Result<Vec<Statement>> {
 	let mut pairs = TokenParser::parse(Rule::file, input).map_err(GicError::from)?;
 
-	let mut expressions = Vec::new();
+	let mut statements = Vec::new();
 
 	let file_pair = pairs.next().unwrap(); // Rule::file
 
 	for clause in file_pair.into_inner() {
-		if clause.as_rule() != Rule::clause {
-			continue;
+		match clause.as_rule() {
+			Rule::clause => {},
+			Rule::table_directive => {
+				statements.push(Statement::Table(parse_table_directive(clause)?));
+				continue;
+			},
+			_ => continue,
 		}
 
 		let formula_pair = clause.into_inner().next().unwrap();
@@ -51,10 +68,24 @@ pub fn parse_gic_file(input: &str) -> Result<Vec<Expression>> {
 			.parse(&mut expr_children)
 			.map_err(|e| GicError::ParseError(format!("Pratt parser error: {:?}", e)))?;
 
-		expressions.push(parsed_expr);
+		statements.push(Statement::Formula(parsed_expr));
 	}
 
-	Ok(expressions)
+	Ok(statements)
+}
+
+fn parse_table_directive(pair: Pair<Rule>) -> Result<Vec<(String, usize)>> {
+	pair.into_inner()
+		.map(|spec| {
+			let mut inner = spec.into_inner();
+			let name = inner.next().unwrap().as_str().to_string();
+			let arity = inner.next().unwrap().as_str();
+			let arity = arity
+				.parse()
+				.map_err(|_| GicError::SemanticError(format!("Invalid arity: {}", arity)))?;
+			Ok((name, arity))
+		})
+		.collect()
 }
 
 struct GicParser;
@@ -68,7 +99,7 @@ where
 
 	fn query(&mut self, pair: &Self::Input) -> Result<Affix> {
 		match pair.as_rule() {
-			Rule::not_op => Ok(Affix::Prefix(Precedence(2))),
+			Rule::not_op | Rule::naf_op => Ok(Affix::Prefix(Precedence(2))),
 			Rule::quantifier_expr => {
 				let s = pair.as_str().trim();
 				// quantifier_expr has format: "<quant> <var>."
@@ -80,9 +111,12 @@ where
 					_ => Err(GicError::SemanticError(format!("Unknown quantifier: {}", quant))),
 				}
 			},
-			Rule::and_op => Ok(Affix::Infix(Precedence(5), Associativity::Left)),
-			Rule::or_op => Ok(Affix::Infix(Precedence(6), Associativity::Left)),
-			Rule::impl_op => Ok(Affix::Infix(Precedence(7), Associativity::Right)),
+			// Looser than `and`, so that `H if B1 and B2` is a rule with the body `B1 and B2`.
+			Rule::iff_op => Ok(Affix::Infix(Precedence(5), Associativity::Right)),
+			Rule::rimpl_op => Ok(Affix::Infix(Precedence(6), Associativity::Left)),
+			Rule::and_op => Ok(Affix::Infix(Precedence(7), Associativity::Left)),
+			Rule::or_op | Rule::xor_op => Ok(Affix::Infix(Precedence(8), Associativity::Left)),
+			Rule::impl_op => Ok(Affix::Infix(Precedence(9), Associativity::Right)),
 			_ => Ok(Affix::Nilfix),
 		}
 	}
@@ -90,7 +124,15 @@ where
 	fn primary(&mut self, pair: Pair<Rule>) -> Result<Expression> {
 		match pair.as_rule() {
 			Rule::predicate => Ok(Expression::Proposition(parse_proposition(pair)?)),
+			Rule::equation => {
+				let terms: Result<Vec<Term>> = pair.into_inner().map(parse_term).collect();
+				Ok(Expression::Proposition(Proposition {
+					name: EQUALITY.to_string(),
+					terms: terms?,
+				}))
+			},
 			Rule::bottom => Ok(Expression::Bottom),
+			Rule::top => Ok(Expression::Top),
 			Rule::group => {
 				let inner_expr = pair.into_inner().next().unwrap(); // Rule::expr
 				self.parse(&mut inner_expr.into_inner())
@@ -109,9 +151,10 @@ where
 	fn prefix(&mut self, pair: Pair<Rule>, rhs: Expression) -> Result<Expression> {
 		match pair.as_rule() {
 			Rule::not_op => Ok(Expression::Not(Box::new(rhs))),
+			Rule::naf_op => Ok(Expression::NegationAsFailure(Box::new(rhs))),
 			Rule::quantifier_expr => {
 				// Manually parse inner parts from the string
-				let s = pair.as_str();
+				let s = pair.as_str().trim();
 				let s = s.trim_end_matches('.'); // remove the trailing dot
 				let (quant, var) = s.split_once(' ').ok_or_else(|| {
 					GicError::SemanticError(format!("Malformed quantifier: {}", s))
@@ -134,7 +177,10 @@ where
 		match op.as_rule() {
 			Rule::and_op => Ok(Expression::And(Box::new(lhs), Box::new(rhs))),
 			Rule::or_op => Ok(Expression::Or(Box::new(lhs), Box::new(rhs))),
+			Rule::xor_op => Ok(Expression::Xor(Box::new(lhs), Box::new(rhs))),
 			Rule::impl_op => Ok(Expression::Implies(Box::new(lhs), Box::new(rhs))),
+			Rule::rimpl_op => Ok(Expression::ImpliedBy(Box::new(lhs), Box::new(rhs))),
+			Rule::iff_op => Ok(Expression::Iff(Box::new(lhs), Box::new(rhs))),
 			_ => Err(GicError::SemanticError(format!(
 				"Unexpected rule in infix: {:?}",
 				op.as_rule()
@@ -221,8 +267,8 @@ mod tests {
 
 	fn parse_list(input: &str) -> Term {
 		// We cheat by parsing a dummy predicate around the list,
-		// e.g. "p(<list>)", then extracting the sole term.
-		let wrapped = format!("p({});", input);
+		// e.g. "P(<list>).", then extracting the sole term.
+		let wrapped = format!("P({}).", input);
 		let mut exprs = parse_gic_file(&wrapped).unwrap();
 		if let Expression::Proposition(prop) = exprs.pop().unwrap() {
 			prop.terms.into_iter().next().unwrap()
@@ -241,20 +287,20 @@ mod tests {
 
 	#[test]
 	fn test_plain_list() {
-		// [a,b,c] → cons(a, cons(b, cons(c, empty_list())))
-		let t = parse_list("[a,b,c]");
+		// [A,B,C] → cons(A, cons(B, cons(C, empty_list())))
+		let t = parse_list("[A,B,C]");
 		let expected = Term::FunctionApplication {
 			name: "cons".into(),
 			args: vec![
-				Term::Identifier("a".into()),
+				Term::Identifier("A".into()),
 				Term::FunctionApplication {
 					name: "cons".into(),
 					args: vec![
-						Term::Identifier("b".into()),
+						Term::Identifier("B".into()),
 						Term::FunctionApplication {
 							name: "cons".into(),
 							args: vec![
-								Term::Identifier("c".into()),
+								Term::Identifier("C".into()),
 								Term::FunctionApplication {
 									name: "empty_list".into(),
 									args: vec![],
@@ -270,10 +316,10 @@ mod tests {
 
 	#[test]
 	fn test_list_cons_with_list_tail() {
-		// [a|[b,c]] → cons(a, cons(b, cons(c, empty_list())))
-		let t = parse_list("[a|[b,c]]");
+		// [A|[B,C]] → cons(A, cons(B, cons(C, empty_list())))
+		let t = parse_list("[A|[B,C]]");
 		// it should flatten into the same as above
-		assert_eq!(format!("{}", t), "[a, b, c]");
+		assert_eq!(format!("{}", t), "[A, B, C]");
 	}
 
 	#[test]
@@ -288,4 +334,41 @@ mod tests {
 			}
 		);
 	}
+
+	#[test]
+	fn test_equation() {
+		let exprs = parse_gic_file("f(X) = b. P(a) => a = b.").unwrap();
+		assert_eq!(exprs[0].to_string(), "f(X) = b()");
+		assert!(matches!(&exprs[1], Expression::Implies(..)));
+		assert_eq!(exprs[1].to_string(), "(P(a()) => a() = b())");
+	}
+
+	#[test]
+	fn test_predicate_without_arguments() {
+		let exprs = parse_gic_file("Rain impl Wet. X = Y.").unwrap();
+		assert_eq!(exprs[0].to_string(), "(Rain => Wet)");
+		let Expression::Proposition(prop) = &exprs[1] else {
+			panic!("Expected proposition");
+		};
+		assert_eq!(
+			prop.as_equation().map(|(l, r)| (l.to_string(), r.to_string())),
+			Some(("X".into(), "Y".into()))
+		);
+	}
+
+	#[test]
+	fn test_new_connectives() {
+		let parse = |formula: &str| parse_formula(&format!("{};", formula)).unwrap().to_string();
+		assert_eq!(
+			parse("Grandpa(X, Y) if Father(X, Z) and Father(Z, Y)"),
+			"(Grandpa(X, Y) <= (Father(X, Z) ∧ Father(Z, Y)))"
+		);
+		assert_eq!(parse("P <= Q ⇐ R"), "((P <= Q) <= R)");
+		assert_eq!(parse("P iff Q if R"), "(P <=> (Q <= R))");
+		assert_eq!(parse("P impl Q iff (not Q) impl (not P)"), "((P => Q) <=> (¬Q => ¬P))");
+		assert_eq!(parse("P ⇔ Q <=> R"), "(P <=> (Q <=> R))");
+		assert_eq!(parse("P xor Q or R"), "((P ⊕ Q) ∨ R)");
+		assert_eq!(parse("forall X. P(X) if Q(X)"), "∀X: (P(X) <= Q(X))");
+		assert_eq!(parse("top ∨ ⊤ ⊕ ⊥"), "((⊤ ∨ ⊤) ⊕ ⊥)");
+	}
 }
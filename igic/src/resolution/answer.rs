@@ -0,0 +1,93 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::mgu::bindings::Bindings;
+use crate::types::ast::Term;
+
+/// A single solution to a query: the value bound to each free variable of the goal.
+///
+/// Values are fully resolved. A variable that is only an alias of an earlier query variable is
+/// bound to that variable's name, and is shown as `X = Y`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Answer {
+	pub bindings: Vec<(String, Term)>,
+}
+
+impl Answer {
+	/// Reads the values of the query variables from the bindings of a successful derivation, the
+	/// variable numbered `i` being named `query_vars[i]`.
+	///
+	/// Every unbound variable that is the value of a query variable is named after the first such
+	/// query variable, so aliased query variables share a name and unbound ones are left out. The
+	/// other variables created during the derivation are named `_G1`, `_G2`, ... in order of
+	/// appearance.
+	pub fn from_bindings(query_vars: &[String], bindings: &Bindings) -> Self {
+		let values: Vec<Term> =
+			(0..query_vars.len()).map(|id| bindings.resolve(&Term::Var(id))).collect();
+
+		let mut names = HashMap::new();
+		for (var, value) in query_vars.iter().zip(&values) {
+			if let Term::Var(id) = value {
+				names.entry(*id).or_insert_with(|| var.clone());
+			}
+		}
+		// A bound query variable can still occur in its own value when the binding is cyclic.
+		for (id, var) in query_vars.iter().enumerate() {
+			names.entry(id).or_insert_with(|| var.clone());
+		}
+
+		let mut fresh = 0;
+		let mut answer = Vec::new();
+		for (var, value) in query_vars.iter().zip(&values) {
+			let value = name_vars(value, &mut names, &mut fresh);
+			if !matches!(&value, Term::Identifier(name) if name == var) {
+				answer.push((var.clone(), value));
+			}
+		}
+		Answer { bindings: answer }
+	}
+
+	pub fn get(&self, var: &str) -> Option<&Term> {
+		self.bindings.iter().find(|(name, _)| name == var).map(|(_, value)| value)
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.bindings.is_empty()
+	}
+}
+
+/// Replaces the numbered variables of `term` by their names in `names`, naming the variables not
+/// in it `_G1`, `_G2`, ... by counting them in `fresh`.
+fn name_vars(term: &Term, names: &mut HashMap<usize, String>, fresh: &mut usize) -> Term {
+	match term {
+		Term::Var(id) => {
+			let name = names.entry(*id).or_insert_with(|| {
+				*fresh += 1;
+				format!("_G{}", fresh)
+			});
+			Term::Identifier(name.clone())
+		},
+		Term::FunctionApplication { name, args } => Term::FunctionApplication {
+			name: name.clone(),
+			args: args.iter().map(|arg| name_vars(arg, names, fresh)).collect(),
+		},
+		Term::Identifier(_) | Term::Number(_) => term.clone(),
+	}
+}
+
+impl fmt::Display for Answer {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		if self.bindings.is_empty() {
+			return write!(f, "true.");
+		}
+		let bindings: Vec<String> = self
+			.bindings
+			.iter()
+			.map(|(var, value)| match value {
+				Term::Identifier(alias) => format!("{} = {}", alias, var),
+				_ => format!("{} := {}", var, value),
+			})
+			.collect();
+		write!(f, "{}", bindings.join(", "))
+	}
+}
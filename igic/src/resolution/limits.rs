@@ -0,0 +1,49 @@
+use std::fmt;
+use std::time::Duration;
+
+/// Bounds on the work done by SLD resolution. `None` means unbounded.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Limits {
+	/// Maximum number of resolution steps in a single derivation. Deeper branches are pruned and
+	/// the rest of the search continues.
+	pub max_depth: Option<usize>,
+	/// Maximum number of inference steps for the whole query.
+	pub max_steps: Option<usize>,
+	/// Maximum wall-clock time for the whole query, counted from the moment it starts.
+	pub timeout: Option<Duration>,
+}
+
+impl Limits {
+	pub fn unbounded() -> Self {
+		Limits::default()
+	}
+}
+
+/// The limit that stopped, or pruned, a search.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LimitExceeded {
+	Depth(usize),
+	Steps(usize),
+	Time(Duration),
+}
+
+impl LimitExceeded {
+	/// A short, stable name for the limit, used in machine-readable output.
+	pub fn kind(&self) -> &'static str {
+		match self {
+			LimitExceeded::Depth(_) => "depth",
+			LimitExceeded::Steps(_) => "steps",
+			LimitExceeded::Time(_) => "time",
+		}
+	}
+}
+
+impl fmt::Display for LimitExceeded {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			LimitExceeded::Depth(n) => write!(f, "maximum derivation depth of {} exceeded", n),
+			LimitExceeded::Steps(n) => write!(f, "maximum of {} inference steps exceeded", n),
+			LimitExceeded::Time(t) => write!(f, "time limit of {:?} exceeded", t),
+		}
+	}
+}
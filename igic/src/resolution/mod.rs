@@ -1 +1,9 @@
+pub mod answer;
+pub mod limits;
+pub mod ordering;
+#[allow(clippy::module_inception)]
 pub mod resolution;
+pub mod saturation;
+pub mod tabling;
+#[cfg(test)]
+pub(crate) mod testing;
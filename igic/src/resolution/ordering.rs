@@ -0,0 +1,95 @@
+use std::cmp::Ordering;
+
+use crate::types::ast::Term;
+
+/// The precedence of the head symbols of `s` and `t`, neither a variable. Function symbols are
+/// ordered by arity, then by name, and numbers are below every function symbol.
+fn precedence(s: &Term, t: &Term) -> Ordering {
+	match (s, t) {
+		(Term::Number(n1), Term::Number(n2)) => n1.cmp(n2),
+		(Term::Number(_), _) => Ordering::Less,
+		(_, Term::Number(_)) => Ordering::Greater,
+		(
+			Term::FunctionApplication { name: n1, args: a1 },
+			Term::FunctionApplication { name: n2, args: a2 },
+		) => a1.len().cmp(&a2.len()).then_with(|| n1.cmp(n2)),
+		_ => Ordering::Equal,
+	}
+}
+
+fn is_var(term: &Term) -> bool {
+	matches!(term, Term::Var(_) | Term::Identifier(_))
+}
+
+fn occurs(var: &Term, term: &Term) -> bool {
+	match term {
+		Term::FunctionApplication { args, .. } => args.iter().any(|arg| occurs(var, arg)),
+		_ => var == term,
+	}
+}
+
+fn args(term: &Term) -> &[Term] {
+	match term {
+		Term::FunctionApplication { args, .. } => args,
+		_ => &[],
+	}
+}
+
+/// Returns true if `s` is greater than `t` in the lexicographic path ordering.
+///
+/// The ordering is a simplification ordering that is total on ground terms, and it is stable
+/// under substitution: if `s > t` then `sσ > tσ`. Equations are only used to replace the greater
+/// side by the smaller one, which keeps paramodulation from rewriting terms back and forth.
+pub fn lpo_greater(s: &Term, t: &Term) -> bool {
+	if is_var(s) {
+		return false;
+	}
+	if is_var(t) {
+		return s != t && occurs(t, s);
+	}
+	// Some argument of `s` is greater than or equal to `t`.
+	if args(s).iter().any(|arg| arg == t || lpo_greater(arg, t)) {
+		return true;
+	}
+	let dominates = || args(t).iter().all(|arg| lpo_greater(s, arg));
+	match precedence(s, t) {
+		Ordering::Greater => dominates(),
+		Ordering::Equal => {
+			let first_difference = args(s).iter().zip(args(t)).find(|(a, b)| a != b);
+			match first_difference {
+				Some((a, b)) => lpo_greater(a, b) && dominates(),
+				None => false,
+			}
+		},
+		Ordering::Less => false,
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn app(name: &str, args: Vec<Term>) -> Term {
+		Term::FunctionApplication { name: name.to_string(), args }
+	}
+
+	#[test]
+	fn test_lpo() {
+		let (x, y, z) = (Term::Var(0), Term::Var(1), Term::Var(2));
+		let e = app("e", vec![]);
+		let mul = |a: Term, b: Term| app("mul", vec![a, b]);
+
+		assert!(lpo_greater(&mul(e.clone(), x.clone()), &x));
+		assert!(!lpo_greater(&x, &mul(e.clone(), x.clone())));
+		assert!(!lpo_greater(&mul(x.clone(), y.clone()), &z));
+		assert!(lpo_greater(&app("inv", vec![e.clone()]), &e));
+		// Associativity is oriented to the right.
+		let left = mul(mul(x.clone(), y.clone()), z.clone());
+		let right = mul(x.clone(), mul(y.clone(), z.clone()));
+		assert!(lpo_greater(&left, &right));
+		assert!(!lpo_greater(&right, &left));
+		// Commutativity cannot be oriented.
+		assert!(!lpo_greater(&mul(x.clone(), y.clone()), &mul(y.clone(), x.clone())));
+		assert!(!lpo_greater(&mul(y.clone(), x.clone()), &mul(x, y)));
+	}
+}
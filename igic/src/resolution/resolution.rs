@@ -1,155 +1,767 @@
-use colored::*;
-use std::collections::VecDeque;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Instant;
 
-use rustyline::history::FileHistory;
-use rustyline::Editor;
+use crate::libraries::{Branches, BuiltinRegistry};
 
-use crate::libraries::built_in_preds;
+use crate::mgu::bindings::Bindings;
+use crate::mgu::mgu::{MguError, OccursCheck};
 
-use crate::mgu::mgu::{mgu, Result, Substitution, Unifiable, UnificationEquation};
-use crate::mgu::substitution::{
-	apply_substitution_to_clause, compose_substitutions, empty_substitution,
-};
-
-use crate::types::ast::Term;
+use crate::resolution::answer::Answer;
+use crate::resolution::limits::{LimitExceeded, Limits};
+use crate::resolution::tabling::{variant, TableAnswers, Tables};
+use crate::types::ast::{Proposition, Term};
 use crate::types::clause::{Clause, Literal, Program};
+use crate::types::{GicError, Result};
 
-pub fn sld_resolution(program: &Program, goal: &Clause, rl: &mut Editor<(), FileHistory>) {
+/// Starts SLD resolution of `goal` against `program`, running the predicates in `builtins` natively.
+///
+/// The returned [`Solver`] is a lazy iterator: each call to `next` resumes the search until the
+/// next solution is found, so the caller decides how many answers to ask for.
+pub fn sld_resolution<'p>(
+	program: &'p Program,
+	builtins: &'p BuiltinRegistry,
+	goal: &Clause,
+) -> Result<Solver<'p>> {
 	if goal.is_empty() {
-		eprintln!("Goal is empty, no resolution needed.");
-		return;
+		return Err(GicError::ResolutionError("Goal is empty, no resolution needed.".to_string()));
 	}
 	if !goal.is_goal() {
-		eprintln!("Goal is not a valid goal clause.");
-		return;
+		return Err(GicError::ResolutionError("Goal is not a valid goal clause.".to_string()));
 	}
-	if program.0.is_empty() {
-		eprintln!("Program is empty, no clauses to resolve.");
-		return;
+	if program.is_empty() {
+		return Err(GicError::ResolutionError(
+			"Program is empty, no clauses to resolve.".to_string(),
+		));
 	}
 	if !program.is_horn() {
-		eprintln!("Program is not a Horn clause program, SLD resolution not applicable.");
-		return;
-	}
-
-	let free_vars = goal.fv();
-	let free_var_terms = free_vars
-		.iter()
-		.map(|var| Unifiable::Term(Term::Identifier(var.clone())))
-		.collect::<Vec<_>>();
-
-	let mut stack: VecDeque<(Clause, Substitution)> = VecDeque::new();
-	let mut clause_counter = 1;
-	stack.push_back((goal.clone(), empty_substitution()));
-
-	while let Some((current_goal, current_sub)) = stack.pop_back() {
-		if let Some(goal_literal) = current_goal.0.first() {
-			if let Some(branches) = built_in_preds(&current_goal, goal_literal, &current_sub) {
-				for (new_goal, new_sub) in branches {
-					if let Some(new_goal_literal) = new_goal.0.first() {
-						sld_backtrack(
-							&new_goal,
-							new_goal_literal,
-							&new_sub,
-							program,
-							&mut clause_counter,
-							&mut stack,
-							&free_var_terms,
-							rl,
-						);
-					} else {
-						print_solutions(&free_var_terms, &new_sub);
-						match continue_prompt(rl) {
-							true => continue,
-							false => return,
-						}
-					}
+		return Err(GicError::ResolutionError(
+			"Program is not a Horn clause program, SLD resolution not applicable.".to_string(),
+		));
+	}
+
+	// The variables of the query are numbered first, so that they keep their names in answers.
+	let (goal, names) = goal.number_vars(0);
+	let mut query_vars = vec![String::new(); names.len()];
+	for (name, index) in names {
+		query_vars[index] = name.to_string();
+	}
+
+	Ok(Solver {
+		program,
+		builtins,
+		next_var: query_vars.len(),
+		query_vars,
+		current: Some((goal, 0)),
+		stack: Vec::new(),
+		bindings: Bindings::new(),
+		limits: Limits::unbounded(),
+		occurs_check: OccursCheck::default(),
+		started: Instant::now(),
+		steps: 0,
+		limit_exceeded: None,
+		interrupt: None,
+		interrupted: false,
+		error: None,
+		warnings: Vec::new(),
+		tables: Rc::new(RefCell::new(Tables::new())),
+	})
+}
+
+/// An untried alternative of the search, tagged with the depth of the derivation it belongs to
+/// and the trail mark to undo the bindings to before trying it.
+///
+/// Alternatives are only computed when the search backtracks into them, and the entry on top of
+/// the stack is always the leftmost unexplored branch of the SLD tree. Answers are therefore found
+/// in the same order as in Prolog: goals are solved left to right, and the program clauses for a
+/// goal are tried top to bottom, in the order they were loaded.
+enum ChoicePoint<'p> {
+	/// A goal whose first literal is being resolved against its candidate program clauses,
+	/// starting from the candidate at index `next`.
+	Clauses { goal: Clause, depth: usize, candidates: &'p [usize], next: usize, mark: usize },
+	/// The untried alternatives of the builtin predicate called by the first literal of `goal`.
+	Branches { goal: Clause, branches: Branches, depth: usize, mark: usize },
+}
+
+/// A suspended SLD derivation, yielding one [`Answer`] per solution.
+pub struct Solver<'p> {
+	program: &'p Program,
+	builtins: &'p BuiltinRegistry,
+	/// The names of the query variables, the variable numbered `i` being at index `i`.
+	query_vars: Vec<String>,
+	/// The number of the next variable created when renaming a clause apart.
+	next_var: usize,
+	/// The goal to work on next, with its depth. `None` means the search has to backtrack.
+	current: Option<(Clause, usize)>,
+	stack: Vec<ChoicePoint<'p>>,
+	bindings: Bindings,
+	limits: Limits,
+	occurs_check: OccursCheck,
+	started: Instant,
+	steps: usize,
+	limit_exceeded: Option<LimitExceeded>,
+	interrupt: Option<Arc<AtomicBool>>,
+	interrupted: bool,
+	error: Option<GicError>,
+	warnings: Vec<String>,
+	/// The answer tables of the query, shared with its sub-derivations.
+	tables: Rc<RefCell<Tables>>,
+}
+
+impl<'p> Solver<'p> {
+	/// Bounds the search with `limits`. The time limit counts from this call.
+	pub fn with_limits(mut self, limits: Limits) -> Self {
+		self.limits = limits;
+		self.started = Instant::now();
+		self
+	}
+
+	/// Sets how unification treats cyclic bindings such as `X = f(X)`. With
+	/// [`OccursCheck::Error`], the first one stops the search and is reported by [`Solver::error`].
+	pub fn with_occurs_check(mut self, occurs_check: OccursCheck) -> Self {
+		self.occurs_check = occurs_check;
+		self
+	}
+
+	/// Stops the search as soon as `flag` is set, e.g. from a signal handler. The flag is not reset
+	/// by the solver.
+	pub fn with_interrupt(mut self, flag: Arc<AtomicBool>) -> Self {
+		self.interrupt = Some(flag);
+		self
+	}
+
+	/// Returns true if the search was stopped by the interrupt flag.
+	pub fn interrupted(&self) -> bool {
+		self.interrupted
+	}
+
+	/// The limit that stopped or pruned the search, if any. When this is set after the iterator
+	/// is exhausted, the answers found may not be all the solutions of the query.
+	pub fn limit_exceeded(&self) -> Option<LimitExceeded> {
+		self.limit_exceeded
+	}
+
+	/// The error that stopped the search, if any. The answers found before it are still valid.
+	pub fn error(&self) -> Option<&GicError> {
+		self.error.as_ref()
+	}
+
+	/// The warnings raised so far, each one only once, such as calls to negation as failure with
+	/// unbound variables.
+	pub fn warnings(&self) -> &[String] {
+		&self.warnings
+	}
+
+	fn warn(&mut self, warning: String) {
+		if !self.warnings.contains(&warning) {
+			self.warnings.push(warning);
+		}
+	}
+
+	/// Counts one inference step, returning `false` if the search was interrupted or the step or
+	/// time limits are exceeded.
+	fn step(&mut self) -> bool {
+		if self.interrupt.as_ref().is_some_and(|flag| flag.load(Ordering::Relaxed)) {
+			self.interrupted = true;
+			return false;
+		}
+		self.steps += 1;
+		if let Some(max_steps) = self.limits.max_steps {
+			if self.steps > max_steps {
+				self.limit_exceeded = Some(LimitExceeded::Steps(max_steps));
+				return false;
+			}
+		}
+		if let Some(timeout) = self.limits.timeout {
+			if self.started.elapsed() > timeout {
+				self.limit_exceeded = Some(LimitExceeded::Time(timeout));
+				return false;
+			}
+		}
+		true
+	}
+
+	/// Handles a failed unification, whose bindings have already been undone. Returns true if it
+	/// was an occurs check failure that stops the search.
+	fn unification_failed(&mut self, e: MguError) -> bool {
+		match e {
+			MguError::OccursCheck(msg) if self.occurs_check == OccursCheck::Error => {
+				self.error =
+					Some(GicError::ResolutionError(format!("Occurs check failed: {}", msg)));
+				self.stack.clear();
+				true
+			},
+			_ => false,
+		}
+	}
+
+	/// The program clauses that may resolve the first literal of `goal`, found through the index
+	/// of the program by predicate and first argument.
+	fn candidates(&self, goal: &Clause) -> &'p [usize] {
+		match goal.0.first() {
+			Some(Literal::Not(prop)) => {
+				let first_arg = prop.terms.first().map(|t| self.bindings.walk(t));
+				self.program.candidates(&prop.name, prop.terms.len(), first_arg)
+			},
+			_ => &[],
+		}
+	}
+
+	/// Resolves the first literal of `goal` against the program clauses in `candidates` from index
+	/// `next` on, making the first resolvent found the current goal and pushing a choice point for
+	/// the candidates after it.
+	fn resolve(&mut self, goal: Clause, depth: usize, candidates: &'p [usize], next: usize) {
+		let clauses = self.program.clauses();
+		let mark = self.bindings.mark();
+		let goal_literal = match goal.0.first() {
+			Some(literal) => literal,
+			None => return,
+		};
+
+		for (i, &index) in candidates.iter().enumerate().skip(next) {
+			let (std_clause, names) = clauses[index].number_vars(self.next_var);
+			let Some(literal) = std_clause.0.first() else {
+				continue;
+			};
+			let unified =
+				unify_literals(&mut self.bindings, goal_literal, literal, self.occurs_check);
+			if let Err(e) = unified {
+				self.bindings.undo_to(mark);
+				if self.unification_failed(e) {
+					return;
 				}
 				continue;
 			}
-			sld_backtrack(
-				&current_goal,
-				goal_literal,
-				&current_sub,
-				program,
-				&mut clause_counter,
-				&mut stack,
-				&free_var_terms,
-				rl,
-			);
+			self.next_var += names.len();
+
+			let mut new_goal_lits = std_clause.0[1..].to_vec();
+			new_goal_lits.extend(goal.iter().skip(1).cloned());
+
+			if i + 1 < candidates.len() {
+				self.stack.push(ChoicePoint::Clauses {
+					goal,
+					depth,
+					candidates,
+					next: i + 1,
+					mark,
+				});
+			}
+			if self.step() {
+				self.current = Some((Clause(new_goal_lits), depth + 1));
+			}
+			return;
+		}
+	}
+
+	/// Runs `goal`, the goal of a negation as failure, as a separate derivation from the current
+	/// bindings. Returns true if it has no solution, so that the negation holds.
+	///
+	/// The sub-derivation shares the limits and the step count of this one. If it fails only
+	/// because a limit pruned it, the negation is not proved and the limit is reported.
+	fn negation_as_failure(&mut self, goal: &Clause, depth: usize) -> bool {
+		let goal = Clause(goal.iter().map(|lit| self.bindings.resolve_literal(lit)).collect());
+		if !goal.iter().all(Literal::is_ground) {
+			// Negation as failure only agrees with logical negation on ground goals: `\+ P(X)`
+			// fails as soon as some `X` satisfies `P`, it does not find an `X` that does not.
+			let anonymous = Clause(goal.iter().map(anonymous).collect());
+			self.warn(format!(
+				"Negation as failure called on a goal with unbound variables: \\+({})",
+				anonymous.goal_str()
+			));
+		}
+
+		let mut sub = self.sub_solver();
+		sub.current = Some((goal, depth));
+		let solved = sub.solve();
+		let limit_exceeded = sub.limit_exceeded;
+		if self.absorb(sub) {
+			return false;
+		}
+		if !solved {
+			if let Some(limit) = limit_exceeded {
+				self.limit_exceeded = Some(limit);
+				return false;
+			}
+		}
+		!solved
+	}
+
+	/// A solver for a separate derivation, with no goal yet, which shares the program,
+	/// the settings, the step count and the answer tables of this one. Its variables are numbered
+	/// after those of this derivation, so that goals resolved from the current bindings can be
+	/// passed to it as they are.
+	fn sub_solver(&self) -> Solver<'p> {
+		Solver {
+			program: self.program,
+			builtins: self.builtins,
+			query_vars: Vec::new(),
+			next_var: self.next_var,
+			current: None,
+			stack: Vec::new(),
+			bindings: Bindings::new(),
+			limits: self.limits,
+			occurs_check: self.occurs_check,
+			started: self.started,
+			steps: self.steps,
+			limit_exceeded: None,
+			interrupt: self.interrupt.clone(),
+			interrupted: false,
+			error: None,
+			warnings: Vec::new(),
+			tables: self.tables.clone(),
+		}
+	}
+
+	/// Takes back the step count, the warnings and the outcome of a finished sub-derivation.
+	/// Returns true if it was stopped by an error or an interrupt, which stop this search too.
+	fn absorb(&mut self, sub: Solver<'p>) -> bool {
+		self.steps = sub.steps;
+		self.interrupted |= sub.interrupted;
+		for warning in sub.warnings {
+			self.warn(warning);
+		}
+		if let Some(e) = sub.error {
+			self.error = Some(e);
+			self.stack.clear();
+			return true;
+		}
+		self.interrupted
+	}
+
+	/// The answers of the tabled call `prop`, as branches unifying its arguments with those of
+	/// each answer. See [`Tables`] for how the answers are computed.
+	fn tabled_call(&mut self, prop: &Proposition, depth: usize) -> Branches {
+		let call = Proposition {
+			name: prop.name.clone(),
+			terms: variant(&self.bindings.resolve_prop(prop).terms),
+		};
+		let table = self.tables.borrow_mut().lookup(&call);
+		let answers = match table {
+			Some(answers) => answers,
+			None => self.evaluate_table(call, depth),
+		};
+		let terms = prop.terms.clone();
+		Box::new(
+			answers
+				.into_iter()
+				.map(move |answer| terms.iter().cloned().zip(answer).collect()),
+		)
+	}
+
+	/// Evaluates the tabled call `call` against the program clauses until an iteration finds no new
+	/// answer for any table, and returns its answers.
+	fn evaluate_table(&mut self, call: Proposition, depth: usize) -> TableAnswers {
+		self.tables.borrow_mut().start(call.clone());
+		// The call is resolved on its own, so its variables are numbered from 0.
+		let mut names = HashMap::new();
+		let numbered = call.number_vars(0, &mut names);
+		let goal = Clause(vec![Literal::Not(numbered.clone())]);
+		let aborted = loop {
+			let answers_before = self.tables.borrow().answer_count();
+
+			let mut sub = self.sub_solver();
+			sub.next_var = names.len();
+			let candidates = sub.candidates(&goal);
+			sub.resolve(goal.clone(), depth, candidates, 0);
+			while sub.solve() {
+				let solved = sub.bindings.resolve_prop(&numbered);
+				self.tables.borrow_mut().add_answer(&call, &solved.terms);
+			}
+			let limit_exceeded = sub.limit_exceeded;
+			if self.absorb(sub) {
+				break true;
+			}
+			if let Some(limit) = limit_exceeded {
+				self.limit_exceeded = Some(limit);
+				if !matches!(limit, LimitExceeded::Depth(_)) {
+					break true;
+				}
+			}
+			if self.tables.borrow().answer_count() == answers_before {
+				break false;
+			}
+		};
+		self.tables.borrow_mut().finish(aborted)
+	}
+
+	/// Takes the first of `branches` whose equations unify, continuing with the rest of `goal` and
+	/// pushing a choice point for the branches after it.
+	fn take_branch(&mut self, goal: Clause, mut branches: Branches, depth: usize, mark: usize) {
+		for branch in branches.by_ref() {
+			// Named variables in a branch are local to it, so they are renamed apart like the
+			// variables of a program clause.
+			let mut names = HashMap::new();
+			let branch: Vec<(Term, Term)> = branch
+				.iter()
+				.map(|(t1, t2)| {
+					let t1 = t1.number_vars(self.next_var, &mut names);
+					(t1, t2.number_vars(self.next_var, &mut names))
+				})
+				.collect();
+			self.next_var += names.len();
+
+			let occurs_check = self.occurs_check;
+			let bindings = &mut self.bindings;
+			let unified =
+				branch.iter().try_for_each(|(t1, t2)| bindings.unify(t1, t2, occurs_check));
+			if let Err(e) = unified {
+				self.bindings.undo_to(mark);
+				if self.unification_failed(e) {
+					return;
+				}
+				continue;
+			}
+
+			let rest = Clause(goal.0[1..].to_vec());
+			// Keep the remaining alternatives below the one being explored, so they are only
+			// pulled when backtracking into them.
+			self.stack.push(ChoicePoint::Branches { goal, branches, depth, mark });
+			if self.step() {
+				self.current = Some((rest, depth + 1));
+			}
+			return;
 		}
 	}
-	println!("{}", "false.".red());
-}
 
-fn sld_backtrack(
-	current_goal: &Clause,
-	current_goal_literal: &Literal,
-	current_sub: &Substitution,
-	program: &Program,
-	clause_counter: &mut i32,
-	stack: &mut VecDeque<(Clause, Substitution)>,
-	free_vars_terms: &[Unifiable],
-	rl: &mut Editor<(), FileHistory>,
-) -> () {
-	for clause in &program.0 {
-		*clause_counter += 1;
-		let unique_suffix = format!("_{}", clause_counter);
-		let std_clause = clause.suffix_vars(&unique_suffix);
-		if let Some(literal) = std_clause.0.first() {
-			if let Ok(Some(mgu_sub)) = unify_literals(current_goal_literal, literal) {
-				let mut new_sub = current_sub.clone();
-				compose_substitutions(&mgu_sub, &mut new_sub);
-
-				let mut new_goal_lits = std_clause.0[1..].to_vec();
-				new_goal_lits.extend(current_goal.iter().skip(1).cloned());
-
-				let mut new_goal = Clause(new_goal_lits);
-				if new_goal.is_empty() {
-					print_solutions(&free_vars_terms, &new_sub);
-					match continue_prompt(rl) {
-						true => continue,
-						false => return,
-					}
-				} else {
-					apply_substitution_to_clause(&mgu_sub, &mut new_goal);
-					stack.push_back((new_goal, new_sub));
+	/// Resumes the search until the next solution, leaving its bindings in place. Returns false
+	/// once the search is exhausted or stopped.
+	fn solve(&mut self) -> bool {
+		loop {
+			if self.interrupted
+				|| matches!(
+					self.limit_exceeded,
+					Some(LimitExceeded::Steps(_)) | Some(LimitExceeded::Time(_))
+				) {
+				return false;
+			}
+
+			let Some((current_goal, depth)) = self.current.take() else {
+				// Backtrack into the most recent choice point.
+				let Some(choice_point) = self.stack.pop() else {
+					return false;
+				};
+				match choice_point {
+					ChoicePoint::Clauses { goal, depth, candidates, next, mark } => {
+						self.bindings.undo_to(mark);
+						self.resolve(goal, depth, candidates, next);
+					},
+					ChoicePoint::Branches { goal, branches, depth, mark } => {
+						self.bindings.undo_to(mark);
+						self.take_branch(goal, branches, depth, mark);
+					},
+				}
+				continue;
+			};
+
+			let goal_literal = match current_goal.0.first() {
+				Some(literal) => literal,
+				None => return true,
+			};
+			if let Some(max_depth) = self.limits.max_depth {
+				if depth >= max_depth {
+					self.limit_exceeded = Some(LimitExceeded::Depth(max_depth));
+					continue;
 				}
 			}
+
+			if let Literal::NegationAsFailure(naf_goal) = goal_literal {
+				if self.negation_as_failure(naf_goal, depth) && self.step() {
+					self.current = Some((Clause(current_goal.0[1..].to_vec()), depth + 1));
+				}
+				continue;
+			}
+
+			if let Literal::Not(prop) = goal_literal {
+				if self.program.is_tabled(&prop.name, prop.terms.len()) {
+					let branches = self.tabled_call(prop, depth);
+					let mark = self.bindings.mark();
+					self.take_branch(current_goal, branches, depth, mark);
+					continue;
+				}
+			}
+
+			match self.builtins.call(goal_literal, &self.bindings) {
+				Some(branches) => {
+					let mark = self.bindings.mark();
+					self.take_branch(current_goal, branches, depth, mark);
+				},
+				None => {
+					let candidates = self.candidates(&current_goal);
+					self.resolve(current_goal, depth, candidates, 0);
+				},
+			}
 		}
 	}
 }
 
-fn unify_literals(l1: &Literal, l2: &Literal) -> Result<Option<Substitution>> {
-	match (l1, l2) {
-		(Literal::Proposition(p1), Literal::Not(p2))
-		| (Literal::Not(p1), Literal::Proposition(p2)) => {
-			let eq: UnificationEquation =
-				vec![(Unifiable::Prop(p1.clone()), Unifiable::Prop(p2.clone()))];
-			mgu(eq).map(Some)
-		},
-		_ => Ok(None),
+impl Iterator for Solver<'_> {
+	type Item = Answer;
+
+	fn next(&mut self) -> Option<Answer> {
+		if self.solve() {
+			Some(Answer::from_bindings(&self.query_vars, &self.bindings))
+		} else {
+			None
+		}
 	}
 }
 
-fn print_solutions(free_vars: &[Unifiable], sub: &Substitution) {
-	let mut bindings = Vec::new();
-	for var in free_vars {
-		if let Some(value) = sub.get(var) {
-			bindings.push(format!("{} := {}", var, value));
+/// Replaces the variables of `lit` by `_`, so that the calls of a literal print the same whatever
+/// the numbers of their variables.
+fn anonymous(lit: &Literal) -> Literal {
+	fn term(t: &Term) -> Term {
+		match t {
+			Term::Var(_) | Term::Identifier(_) => Term::Identifier("_".to_string()),
+			Term::FunctionApplication { name, args } => Term::FunctionApplication {
+				name: name.clone(),
+				args: args.iter().map(term).collect(),
+			},
+			Term::Number(_) => t.clone(),
 		}
 	}
-	if bindings.is_empty() {
-		println!("{}", "true.".green());
-	} else {
-		println!("{}", bindings.join(", "));
+	let prop = |p: &Proposition| Proposition {
+		name: p.name.clone(),
+		terms: p.terms.iter().map(term).collect(),
+	};
+	match lit {
+		Literal::Proposition(p) => Literal::Proposition(prop(p)),
+		Literal::Not(p) => Literal::Not(prop(p)),
+		Literal::NegationAsFailure(goal) => {
+			Literal::NegationAsFailure(Clause(goal.iter().map(anonymous).collect()))
+		},
 	}
 }
 
-fn continue_prompt(rl: &mut Editor<(), FileHistory>) -> bool {
-	let readline = rl.readline("Continue? (Y/N) ");
-	match readline {
-		Ok(input) => input.trim().eq_ignore_ascii_case("y"),
-		Err(_) => false, // Exit if there's an error reading input
+fn unify_literals(
+	bindings: &mut Bindings,
+	l1: &Literal,
+	l2: &Literal,
+	occurs_check: OccursCheck,
+) -> std::result::Result<(), MguError> {
+	match (l1, l2) {
+		(Literal::Proposition(p1), Literal::Not(p2))
+		| (Literal::Not(p1), Literal::Proposition(p2)) => bindings.unify_props(p1, p2, occurs_check),
+		_ => Err(MguError::Clash(format!("Literals {} and {} are not complementary", l1, l2))),
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::libraries::standard_builtins;
+	use crate::resolution::testing::{answers, program, program_with_libraries, solve};
+
+	#[test]
+	fn test_depth_limit() {
+		let program = program("(Path(X,Z) and Edge(Z,Y)) impl Path(X,Y). Edge(a,b). Path(X,X).");
+		let builtins = standard_builtins();
+		let limits = Limits { max_depth: Some(20), ..Limits::unbounded() };
+
+		let mut solver = solve(&program, &builtins, "Path(a,b)").with_limits(limits);
+		assert!(solver.next().is_some());
+		while solver.next().is_some() {}
+
+		assert_eq!(solver.limit_exceeded(), Some(LimitExceeded::Depth(20)));
+	}
+
+	#[test]
+	fn test_step_limit() {
+		let program = program("P(a).");
+		let builtins = standard_builtins();
+		let limits = Limits { max_steps: Some(1000), ..Limits::unbounded() };
+
+		let mut solver =
+			solve(&program, &builtins, "Length(XS,N) and Eq(N,-1)").with_limits(limits);
+		assert_eq!(solver.next(), None);
+		assert_eq!(solver.limit_exceeded(), Some(LimitExceeded::Steps(1000)));
+
+		let mut solver = solve(&program, &builtins, "Length([a,b],N)").with_limits(limits);
+		assert!(solver.next().is_some());
+		assert_eq!(solver.next(), None);
+		assert_eq!(solver.limit_exceeded(), None);
+	}
+
+	#[test]
+	fn test_interrupt() {
+		let program = program("P(a).");
+		let builtins = standard_builtins();
+		let flag = Arc::new(AtomicBool::new(false));
+
+		let mut solver = solve(&program, &builtins, "Length(XS,N)").with_interrupt(flag.clone());
+		assert!(solver.next().is_some());
+		flag.store(true, Ordering::Relaxed);
+
+		assert_eq!(solver.next(), None);
+		assert!(solver.interrupted());
+	}
+
+	#[test]
+	fn test_builtin_generators_are_lazy() {
+		let program = program("P(a).");
+		let builtins = standard_builtins();
+
+		let answer = solve(&program, &builtins, "Length(XS,N) and Gt(N,2)").next().unwrap();
+		assert_eq!(answer.get("N"), Some(&Term::Number(3)));
+		let answers = answers(solve(&program, &builtins, "Between(1,4,X) and Gt(X,2)"));
+		assert_eq!(answers, vec!["X := 3", "X := 4"]);
+	}
+
+	#[test]
+	fn test_clauses_are_tried_top_to_bottom() {
+		let program = program("Color(red). Mix(X) impl Color(mixed(X)). Color(green). Mix(blue).");
+		let builtins = standard_builtins();
+
+		let answers = answers(solve(&program, &builtins, "Color(X)").take(4));
+		assert_eq!(answers, vec!["X := red()", "X := mixed(blue())", "X := green()"]);
+	}
+
+	#[test]
+	fn test_goals_are_solved_left_to_right() {
+		let program = program("P(1). P(2). Q(a). Q(b).");
+		let builtins = standard_builtins();
+
+		let answers = answers(solve(&program, &builtins, "P(X) and Q(Y)"));
+		assert_eq!(
+			answers,
+			vec!["X := 1, Y := a()", "X := 1, Y := b()", "X := 2, Y := a()", "X := 2, Y := b()"]
+		);
+	}
+
+	#[test]
+	fn test_occurs_check() {
+		let program = program_with_libraries("Loop(X, s(X)).");
+		let builtins = standard_builtins();
+		let query = "exists X. X = f(X)";
+
+		assert_eq!(answers(solve(&program, &builtins, query)), vec!["X := f(X)"]);
+
+		let mut solver = solve(&program, &builtins, query).with_occurs_check(OccursCheck::On);
+		assert_eq!(solver.next(), None);
+		assert!(solver.error().is_none());
+
+		let mut solver = solve(&program, &builtins, query).with_occurs_check(OccursCheck::Error);
+		assert_eq!(solver.next(), None);
+		assert!(matches!(solver.error(), Some(GicError::ResolutionError(_))));
+
+		// The check also applies to program clauses, and to `=` written the other way round.
+		let checked = |query| solve(&program, &builtins, query).with_occurs_check(OccursCheck::On);
+		assert_eq!(checked("exists X. Loop(X, X)").next(), None);
+		assert!(checked("exists X. Loop(a, X)").next().is_some());
+		assert!(checked("exists X. f(X) = X").next().is_none());
+	}
+
+	#[test]
+	fn test_backtracking_undoes_bindings() {
+		let program = program("P(1). P(2). P(3). Q(2). Q(3).");
+		let builtins = standard_builtins();
+
+		assert_eq!(answers(solve(&program, &builtins, "P(X) and Q(X)")), vec!["X := 2", "X := 3"]);
+	}
+
+	#[test]
+	fn test_fresh_variable_names() {
+		let program = program_with_libraries("");
+		let builtins = standard_builtins();
+
+		let answers = answers(solve(&program, &builtins, "Append(XS, YS, ZS)").skip(1).take(1));
+		assert_eq!(answers, vec!["XS := [_G1], ZS := [_G1|YS]"]);
+		let answer = solve(&program, &builtins, "Length(XS,4) and Reverse(XS,XS)").next().unwrap();
+		assert_eq!(answer.to_string(), "XS := [_G1, _G2, _G2, _G1]");
+	}
+
+	#[test]
+	fn test_nested_query_variables() {
+		let program = program_with_libraries("");
+		let builtins = standard_builtins();
+
+		let answer = solve(&program, &builtins, "f(X, [Y|T]) = f(1, [2, 3])").next().unwrap();
+		assert_eq!(answer.to_string(), "X := 1, Y := 2, T := [3]");
+	}
+
+	#[test]
+	fn test_first_argument_indexing_keeps_order() {
+		let program = program("P(a, 1). P(X, 2). P(b, 3). P(a, 4). P(f(a), 5).");
+		let builtins = standard_builtins();
+
+		assert_eq!(
+			answers(solve(&program, &builtins, "P(a, N)")),
+			vec!["N := 1", "N := 2", "N := 4"]
+		);
+		let answers = answers(solve(&program, &builtins, "P(f(Y), N)"));
+		assert_eq!(answers, vec!["N := 2", "Y := a(), N := 5"]);
+	}
+
+	#[test]
+	fn test_aliased_query_variables() {
+		let program = program_with_libraries("");
+		let builtins = standard_builtins();
+
+		let answer = solve(&program, &builtins, "X = Y").next().unwrap();
+		assert_eq!(answer.to_string(), "X = Y");
+		assert_eq!(answer.get("Y"), Some(&Term::Identifier("X".to_string())));
+
+		let answer = solve(&program, &builtins, "f(X, Y) = f(Z, g(Z))").next().unwrap();
+		assert_eq!(answer.to_string(), "Y := g(X), X = Z");
+
+		let answer = solve(&program, &builtins, "Append(XS, YS, ZS)").next().unwrap();
+		assert_eq!(answer.to_string(), "XS := [], YS = ZS");
+	}
+
+	#[test]
+	fn test_negation_as_failure() {
+		let program = program(
+			"Bird(tweety). Bird(pingu). Penguin(pingu).
+			forall X. ((Bird(X) and \\+ Penguin(X)) => Flies(X)).",
+		);
+		let builtins = standard_builtins();
+
+		assert!(program.is_horn());
+		assert_eq!(answers(solve(&program, &builtins, "Flies(X)")), vec!["X := tweety()"]);
+
+		let mut solver = solve(&program, &builtins, "naf Flies(pingu)");
+		assert_eq!(solver.next().map(|a| a.to_string()), Some("true.".to_string()));
+		assert!(solver.warnings().is_empty());
+		assert_eq!(solve(&program, &builtins, "\\+ (Bird(X) and Penguin(X))").count(), 0);
+	}
+
+	#[test]
+	fn test_negation_as_failure_warns_on_unbound_variables() {
+		let program = program("Bird(tweety). Penguin(pingu).");
+		let builtins = standard_builtins();
+
+		let mut solver = solve(&program, &builtins, "(\\+ Penguin(X)) and Bird(X)");
+		assert!(solver.next().is_none());
+		assert_eq!(
+			solver.warnings(),
+			&["Negation as failure called on a goal with unbound variables: \\+(Penguin(_))"]
+		);
+	}
+
+	#[test]
+	fn test_non_horn_program() {
+		let program = program("P(a) or Q(a). forall X. (P(X) impl R(X)).");
+		let a = Term::FunctionApplication { name: "a".to_string(), args: vec![] };
+		let goal = Clause::from_literals(vec![Literal::Not(Proposition {
+			name: "R".to_string(),
+			terms: vec![a],
+		})]);
+
+		let builtins = standard_builtins();
+		let result = sld_resolution(&program, &builtins, &goal);
+		assert!(matches!(result, Err(GicError::ResolutionError(_))));
+	}
+
+	#[test]
+	fn test_reverse_implication_rules() {
+		let program = program(
+			"Father(juan, pepe). Father(pepe, maria).
+			Grandpa(X, Y) if Father(X, Z) and Father(Z, Y).",
+		);
+		let builtins = standard_builtins();
+
+		assert_eq!(answers(solve(&program, &builtins, "Grandpa(juan, Y)")), vec!["Y := maria()"]);
 	}
 }
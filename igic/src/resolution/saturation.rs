@@ -0,0 +1,819 @@
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap, HashSet};
+use std::fmt;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Instant;
+
+use crate::mgu::bindings::Bindings;
+use crate::mgu::mgu::OccursCheck;
+use crate::resolution::limits::{LimitExceeded, Limits};
+use crate::resolution::ordering::lpo_greater;
+use crate::types::ast::{Proposition, Term, EQUALITY};
+use crate::types::clause::{Clause, Literal};
+use crate::types::{GicError, Result};
+
+/// The outcome of a refutation proof.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProofResult {
+	/// The empty clause was derived: the clauses are unsatisfiable, so the goal follows from the
+	/// program.
+	Proved,
+	/// Every inference was made without deriving the empty clause: the clauses are satisfiable,
+	/// so the goal does not follow from the program.
+	CounterSatisfiable,
+	/// The search was stopped by a limit before reaching either result.
+	Unknown(LimitExceeded),
+	/// The search was stopped by the interrupt flag.
+	Interrupted,
+}
+
+impl fmt::Display for ProofResult {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			ProofResult::Proved => write!(f, "proved"),
+			ProofResult::CounterSatisfiable => write!(f, "counter-satisfiable (saturated)"),
+			ProofResult::Unknown(_) => write!(f, "unknown (limit reached)"),
+			ProofResult::Interrupted => write!(f, "unknown (interrupted)"),
+		}
+	}
+}
+
+/// A clause whose variables are numbered `Term::Var(0)`, `Term::Var(1)`, ... in order of first
+/// appearance, with no repeated literal. Clauses equal up to variable renaming are equal.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct NumberedClause {
+	literals: Vec<Literal>,
+	vars: usize,
+}
+
+impl NumberedClause {
+	fn new(literals: Vec<Literal>) -> Self {
+		let mut names = HashMap::new();
+		let mut numbered: Vec<Literal> = Vec::with_capacity(literals.len());
+		for lit in &literals {
+			let lit = renumber_literal(lit, &mut names);
+			if !numbered.contains(&lit) {
+				numbered.push(lit);
+			}
+		}
+		NumberedClause { literals: numbered, vars: names.len() }
+	}
+
+	fn is_empty(&self) -> bool {
+		self.literals.is_empty()
+	}
+
+	/// The number of symbols in the clause, used to pick light clauses first.
+	fn weight(&self) -> usize {
+		fn term_weight(term: &Term) -> usize {
+			match term {
+				Term::FunctionApplication { args, .. } => {
+					1 + args.iter().map(term_weight).sum::<usize>()
+				},
+				_ => 1,
+			}
+		}
+		self.literals
+			.iter()
+			.map(|lit| 1 + prop(lit).terms.iter().map(term_weight).sum::<usize>())
+			.sum()
+	}
+
+	/// Returns true if the clause holds a literal and its complement, or an equation `t = t`.
+	fn is_tautology(&self) -> bool {
+		self.literals.iter().any(|lit| match lit {
+			Literal::Proposition(p) => {
+				p.as_equation().is_some_and(|(left, right)| left == right)
+					|| self.literals.contains(&Literal::Not(p.clone()))
+			},
+			_ => false,
+		})
+	}
+
+	/// The sides of the clause's only literal if it is a positive equation.
+	fn as_unit_equation(&self) -> Option<(&Term, &Term)> {
+		match self.literals.as_slice() {
+			[Literal::Proposition(p)] => p.as_equation(),
+			_ => None,
+		}
+	}
+
+	/// The literals with every variable shifted by `offset`, to rename the clause apart from
+	/// another one whose variables are below `offset`.
+	fn shifted(&self, offset: usize) -> Vec<Literal> {
+		self.literals
+			.iter()
+			.map(|lit| map_literal(lit, &|id| Term::Var(id + offset)))
+			.collect()
+	}
+
+	/// Returns true if some substitution of the variables of `self` turns its literals into
+	/// distinct literals of `other`, so that `self` is a sub-multiset of `other` up to the
+	/// substitution.
+	fn subsumes(&self, other: &NumberedClause) -> bool {
+		if self.literals.len() > other.literals.len() {
+			return false;
+		}
+		let target = other.shifted(self.vars);
+		let mut used = vec![false; target.len()];
+		match_literals(&self.literals, &target, &mut used, &mut vec![None; self.vars])
+	}
+}
+
+impl fmt::Display for NumberedClause {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(f, "{}", Clause(self.literals.clone()))
+	}
+}
+
+fn prop(lit: &Literal) -> &Proposition {
+	match lit {
+		Literal::Proposition(p) | Literal::Not(p) => p,
+		Literal::NegationAsFailure(_) => unreachable!("negation as failure is rejected on input"),
+	}
+}
+
+fn map_literal(lit: &Literal, var: &dyn Fn(usize) -> Term) -> Literal {
+	fn map_term(term: &Term, var: &dyn Fn(usize) -> Term) -> Term {
+		match term {
+			Term::Var(id) => var(*id),
+			Term::FunctionApplication { name, args } => Term::FunctionApplication {
+				name: name.clone(),
+				args: args.iter().map(|arg| map_term(arg, var)).collect(),
+			},
+			Term::Identifier(_) | Term::Number(_) => term.clone(),
+		}
+	}
+	let map_prop = |p: &Proposition| Proposition {
+		name: p.name.clone(),
+		terms: p.terms.iter().map(|t| map_term(t, var)).collect(),
+	};
+	match lit {
+		Literal::Proposition(p) => Literal::Proposition(map_prop(p)),
+		Literal::Not(p) => Literal::Not(map_prop(p)),
+		Literal::NegationAsFailure(_) => lit.clone(),
+	}
+}
+
+fn renumber_literal(lit: &Literal, names: &mut HashMap<usize, usize>) -> Literal {
+	fn renumber(term: &Term, names: &mut HashMap<usize, usize>) -> Term {
+		match term {
+			Term::Var(id) => {
+				let next = names.len();
+				Term::Var(*names.entry(*id).or_insert(next))
+			},
+			Term::FunctionApplication { name, args } => Term::FunctionApplication {
+				name: name.clone(),
+				args: args.iter().map(|arg| renumber(arg, names)).collect(),
+			},
+			Term::Identifier(_) | Term::Number(_) => term.clone(),
+		}
+	}
+	let mut renumber_prop = |p: &Proposition| Proposition {
+		name: p.name.clone(),
+		terms: p.terms.iter().map(|t| renumber(t, names)).collect(),
+	};
+	match lit {
+		Literal::Proposition(p) => Literal::Proposition(renumber_prop(p)),
+		Literal::Not(p) => Literal::Not(renumber_prop(p)),
+		Literal::NegationAsFailure(_) => lit.clone(),
+	}
+}
+
+/// Matches every literal of `patterns` to a different literal of `targets`, not yet `used`,
+/// binding only the variables of the patterns in `subst`, and backtracking over the choices of
+/// target.
+fn match_literals(
+	patterns: &[Literal],
+	targets: &[Literal],
+	used: &mut [bool],
+	subst: &mut Vec<Option<Term>>,
+) -> bool {
+	let Some((first, rest)) = patterns.split_first() else {
+		return true;
+	};
+	for (i, target) in targets.iter().enumerate() {
+		if used[i] || first.is_positive() != target.is_positive() {
+			continue;
+		}
+		let mut attempt = subst.clone();
+		let (p, t) = (prop(first), prop(target));
+		if p.name == t.name
+			&& p.terms.len() == t.terms.len()
+			&& p.terms.iter().zip(&t.terms).all(|(p, t)| match_term(p, t, &mut attempt))
+		{
+			used[i] = true;
+			if match_literals(rest, targets, used, &mut attempt) {
+				*subst = attempt;
+				return true;
+			}
+			used[i] = false;
+		}
+	}
+	false
+}
+
+fn match_term(pattern: &Term, target: &Term, subst: &mut Vec<Option<Term>>) -> bool {
+	match (pattern, target) {
+		(Term::Var(id), _) if *id < subst.len() => match &subst[*id] {
+			Some(bound) => bound == target,
+			None => {
+				subst[*id] = Some(target.clone());
+				true
+			},
+		},
+		(
+			Term::FunctionApplication { name: n1, args: a1 },
+			Term::FunctionApplication { name: n2, args: a2 },
+		) => {
+			n1 == n2
+				&& a1.len() == a2.len()
+				&& a1.iter().zip(a2).all(|(p, t)| match_term(p, t, subst))
+		},
+		_ => pattern == target,
+	}
+}
+
+/// A saturation prover for clauses in full first-order logic, Horn or not.
+///
+/// The prover runs the given-clause loop: it keeps a set of active clauses, between which every
+/// inference has been made, and a queue of passive clauses. The lightest passive clause is taken
+/// as the given clause and rewritten with the active unit equations. It is discarded if it is
+/// subsumed by an active clause, and otherwise moved to the active set after removing the active
+/// clauses it subsumes. Its inferences with the active clauses become passive, except for
+/// tautologies and clauses already queued. Deriving the empty clause proves the clauses
+/// unsatisfiable, and running out of passive clauses proves them satisfiable.
+///
+/// The inferences are binary resolution and factoring, and for the equality predicate `=`,
+/// paramodulation, equality resolution and equality factoring. Equations are only used from their
+/// greater side to their smaller one in the [lexicographic path ordering](lpo_greater).
+///
+/// Unification always makes the occurs check, which resolution needs to be sound.
+pub struct Prover {
+	active: Vec<NumberedClause>,
+	passive: BinaryHeap<Reverse<(usize, usize)>>,
+	/// The clauses ever added to the passive queue, by id, so that each is queued once.
+	clauses: Vec<NumberedClause>,
+	seen: HashSet<NumberedClause>,
+	limits: Limits,
+	started: Instant,
+	steps: usize,
+	interrupt: Option<Arc<AtomicBool>>,
+}
+
+impl Prover {
+	/// Creates a prover for `clauses`, whose variables are named. Negation as failure has no
+	/// meaning in first-order logic, so clauses using it are rejected.
+	pub fn new<'a>(clauses: impl IntoIterator<Item = &'a Clause>) -> Result<Self> {
+		let mut prover = Prover {
+			active: Vec::new(),
+			passive: BinaryHeap::new(),
+			clauses: Vec::new(),
+			seen: HashSet::new(),
+			limits: Limits::unbounded(),
+			started: Instant::now(),
+			steps: 0,
+			interrupt: None,
+		};
+		for clause in clauses {
+			if clause.iter().any(|lit| matches!(lit, Literal::NegationAsFailure(_))) {
+				return Err(GicError::ResolutionError(format!(
+					"Negation as failure cannot be used in a proof: {}",
+					clause
+				)));
+			}
+			let (numbered, _) = clause.number_vars(0);
+			prover.add_passive(NumberedClause::new(numbered.0));
+		}
+		Ok(prover)
+	}
+
+	/// Bounds the search with `limits`: `max_steps` counts the clauses inferred. The depth limit
+	/// does not apply. The time limit counts from this call.
+	pub fn with_limits(mut self, limits: Limits) -> Self {
+		self.limits = limits;
+		self.started = Instant::now();
+		self
+	}
+
+	/// Stops the search as soon as `flag` is set. The flag is not reset by the prover.
+	pub fn with_interrupt(mut self, flag: Arc<AtomicBool>) -> Self {
+		self.interrupt = Some(flag);
+		self
+	}
+
+	/// The number of clauses inferred so far.
+	pub fn steps(&self) -> usize {
+		self.steps
+	}
+
+	/// Runs the given-clause loop until a result is reached.
+	pub fn run(&mut self) -> ProofResult {
+		while let Some(Reverse((_, id))) = self.passive.pop() {
+			if self.interrupt.as_ref().is_some_and(|flag| flag.load(Ordering::Relaxed)) {
+				return ProofResult::Interrupted;
+			}
+			if let Some(limit) = self.limit_exceeded() {
+				return ProofResult::Unknown(limit);
+			}
+
+			let given = self.demodulate(&self.clauses[id]);
+			if given.is_empty() {
+				return ProofResult::Proved;
+			}
+			if given.is_tautology() {
+				continue;
+			}
+			if self.active.iter().any(|active| active.subsumes(&given)) {
+				continue;
+			}
+			self.active.retain(|active| !given.subsumes(active));
+
+			for factor in factors(&given) {
+				self.infer(factor);
+			}
+			for conclusion in
+				equality_resolvents(&given).into_iter().chain(equality_factors(&given))
+			{
+				self.infer(conclusion);
+			}
+			self.active.push(given.clone());
+			for i in 0..self.active.len() {
+				let active = &self.active[i];
+				let mut conclusions = resolvents(&given, active);
+				conclusions.extend(paramodulants(&given, active));
+				if i + 1 < self.active.len() {
+					conclusions.extend(paramodulants(active, &given));
+				}
+				for conclusion in conclusions {
+					self.infer(conclusion);
+				}
+			}
+		}
+		ProofResult::CounterSatisfiable
+	}
+
+	fn limit_exceeded(&self) -> Option<LimitExceeded> {
+		if let Some(max_steps) = self.limits.max_steps {
+			if self.steps > max_steps {
+				return Some(LimitExceeded::Steps(max_steps));
+			}
+		}
+		if let Some(timeout) = self.limits.timeout {
+			if self.started.elapsed() > timeout {
+				return Some(LimitExceeded::Time(timeout));
+			}
+		}
+		None
+	}
+
+	/// Rewrites `clause` with the active unit equations `l = r` oriented so that `l` is greater than
+	/// `r`, replacing instances `lσ` by `rσ` until none applies. The ordering is stable under
+	/// substitution, so `lσ` is greater than `rσ` and rewriting terminates. It also only puts `l`
+	/// above `r` if every variable of `r` occurs in `l`, so matching `l` binds all of them and the
+	/// variables of `clause` are left alone.
+	fn demodulate(&self, clause: &NumberedClause) -> NumberedClause {
+		let units: Vec<(&Term, &Term)> = self
+			.active
+			.iter()
+			.filter_map(NumberedClause::as_unit_equation)
+			.flat_map(|(left, right)| [(left, right), (right, left)])
+			.filter(|(left, right)| lpo_greater(left, right))
+			.collect();
+		if units.is_empty() {
+			return clause.clone();
+		}
+		let rewrite = |term: &Term| -> Option<Term> {
+			units.iter().find_map(|(left, right)| {
+				let mut subst = vec![None; var_bound(left)];
+				match_term(left, term, &mut subst).then(|| substitute(right, &subst))
+			})
+		};
+		let literals = clause
+			.literals
+			.iter()
+			.map(|lit| {
+				let rewrite_prop = |p: &Proposition| Proposition {
+					name: p.name.clone(),
+					terms: p.terms.iter().map(|t| rewrite_term(t, &rewrite)).collect(),
+				};
+				match lit {
+					Literal::Proposition(p) => Literal::Proposition(rewrite_prop(p)),
+					Literal::Not(p) => Literal::Not(rewrite_prop(p)),
+					Literal::NegationAsFailure(_) => lit.clone(),
+				}
+			})
+			.collect();
+		NumberedClause::new(literals)
+	}
+
+	fn infer(&mut self, clause: NumberedClause) {
+		self.steps += 1;
+		self.add_passive(clause);
+	}
+
+	fn add_passive(&mut self, clause: NumberedClause) {
+		if clause.is_tautology() || self.seen.contains(&clause) {
+			return;
+		}
+		let id = self.clauses.len();
+		// The empty clause is taken first, whatever the other weights.
+		let weight = if clause.is_empty() { 0 } else { clause.weight() };
+		self.passive.push(Reverse((weight, id)));
+		self.seen.insert(clause.clone());
+		self.clauses.push(clause);
+	}
+}
+
+/// The clause obtained by applying `bindings` to `literals`.
+fn instance(literals: impl IntoIterator<Item = Literal>, bindings: &Bindings) -> NumberedClause {
+	NumberedClause::new(literals.into_iter().map(|lit| bindings.resolve_literal(&lit)).collect())
+}
+
+/// The factors of `clause`: for each pair of literals of the same sign that unify, the clause
+/// with the pair merged by their most general unifier.
+fn factors(clause: &NumberedClause) -> Vec<NumberedClause> {
+	let mut factors = Vec::new();
+	let lits = &clause.literals;
+	for i in 0..lits.len() {
+		for j in i + 1..lits.len() {
+			if lits[i].is_positive() != lits[j].is_positive() {
+				continue;
+			}
+			let mut bindings = Bindings::new();
+			if bindings.unify_props(prop(&lits[i]), prop(&lits[j]), OccursCheck::On).is_ok() {
+				let rest = lits.iter().enumerate().filter(|(k, _)| *k != j).map(|(_, l)| l.clone());
+				factors.push(instance(rest, &bindings));
+			}
+		}
+	}
+	factors
+}
+
+/// The binary resolvents of `c1` and `c2`, renamed apart: for each literal of `c1` whose
+/// complement in `c2` unifies with it, the other literals of both under the most general unifier.
+fn resolvents(c1: &NumberedClause, c2: &NumberedClause) -> Vec<NumberedClause> {
+	let mut resolvents = Vec::new();
+	let l2 = c2.shifted(c1.vars);
+	for (i, lit1) in c1.literals.iter().enumerate() {
+		for (j, lit2) in l2.iter().enumerate() {
+			if lit1.is_positive() == lit2.is_positive() {
+				continue;
+			}
+			let mut bindings = Bindings::new();
+			if bindings.unify_props(prop(lit1), prop(lit2), OccursCheck::On).is_err() {
+				continue;
+			}
+			let rest1 = c1.literals.iter().enumerate().filter(|(k, _)| *k != i);
+			let rest2 = l2.iter().enumerate().filter(|(k, _)| *k != j);
+			let rest = rest1.chain(rest2).map(|(_, lit)| lit.clone());
+			resolvents.push(instance(rest, &bindings));
+		}
+	}
+	resolvents
+}
+
+/// One more than the greatest variable number in `term`, or 0 if it has no variable.
+fn var_bound(term: &Term) -> usize {
+	match term {
+		Term::Var(id) => id + 1,
+		Term::FunctionApplication { args, .. } => args.iter().map(var_bound).max().unwrap_or(0),
+		Term::Identifier(_) | Term::Number(_) => 0,
+	}
+}
+
+/// Replaces the variables of `term` bound in `subst`, as filled by [`match_term`].
+fn substitute(term: &Term, subst: &[Option<Term>]) -> Term {
+	match term {
+		Term::Var(id) => match subst.get(*id) {
+			Some(Some(value)) => value.clone(),
+			_ => term.clone(),
+		},
+		Term::FunctionApplication { name, args } => Term::FunctionApplication {
+			name: name.clone(),
+			args: args.iter().map(|arg| substitute(arg, subst)).collect(),
+		},
+		Term::Identifier(_) | Term::Number(_) => term.clone(),
+	}
+}
+
+/// Rewrites `term` innermost first with `rewrite`, until no subterm is rewritten.
+fn rewrite_term(term: &Term, rewrite: &dyn Fn(&Term) -> Option<Term>) -> Term {
+	let term = match term {
+		Term::FunctionApplication { name, args } => Term::FunctionApplication {
+			name: name.clone(),
+			args: args.iter().map(|arg| rewrite_term(arg, rewrite)).collect(),
+		},
+		_ => term.clone(),
+	};
+	match rewrite(&term) {
+		Some(rewritten) => rewrite_term(&rewritten, rewrite),
+		None => term,
+	}
+}
+
+/// The non-variable subterms of `term` with their positions, as paths of argument indices.
+fn subterm_positions(term: &Term, path: &mut Vec<usize>, positions: &mut Vec<Vec<usize>>) {
+	if let Term::FunctionApplication { args, .. } = term {
+		positions.push(path.clone());
+		for (i, arg) in args.iter().enumerate() {
+			path.push(i);
+			subterm_positions(arg, path, positions);
+			path.pop();
+		}
+	} else if let Term::Number(_) = term {
+		positions.push(path.clone());
+	}
+}
+
+fn subterm_at<'a>(term: &'a Term, path: &[usize]) -> &'a Term {
+	match (term, path.split_first()) {
+		(Term::FunctionApplication { args, .. }, Some((i, rest))) => subterm_at(&args[*i], rest),
+		_ => term,
+	}
+}
+
+fn replace_at(term: &Term, path: &[usize], replacement: &Term) -> Term {
+	match (term, path.split_first()) {
+		(Term::FunctionApplication { name, args }, Some((i, rest))) => Term::FunctionApplication {
+			name: name.clone(),
+			args: args
+				.iter()
+				.enumerate()
+				.map(
+					|(j, arg)| {
+						if j == *i {
+							replace_at(arg, rest, replacement)
+						} else {
+							arg.clone()
+						}
+					},
+				)
+				.collect(),
+		},
+		_ => replacement.clone(),
+	}
+}
+
+/// The orientations `(l, r)` of the positive equations of `lits` that may be used to replace `l`
+/// by `r`, with the index of their literal. The left side must not be a variable.
+fn oriented_equations(lits: &[Literal]) -> Vec<(usize, &Term, &Term)> {
+	let mut equations = Vec::new();
+	for (i, lit) in lits.iter().enumerate() {
+		if let Literal::Proposition(p) = lit {
+			if let Some((left, right)) = p.as_equation() {
+				for (l, r) in [(left, right), (right, left)] {
+					if !matches!(l, Term::Var(_)) && !lpo_greater(r, l) {
+						equations.push((i, l, r));
+					}
+				}
+			}
+		}
+	}
+	equations
+}
+
+/// The paramodulants of `from` into `into`, renamed apart: for each equation `l = r` of `from`
+/// and each non-variable subterm `s` of `into` that unifies with `l`, the clause `into` with `s`
+/// replaced by `r`, together with the other literals of `from`, under the unifier. Equations
+/// whose instance `rσ` is greater than `lσ` are not used that way.
+fn paramodulants(from: &NumberedClause, into: &NumberedClause) -> Vec<NumberedClause> {
+	let mut paramodulants = Vec::new();
+	let targets = into.shifted(from.vars);
+	for (i, l, r) in oriented_equations(&from.literals) {
+		for (j, target) in targets.iter().enumerate() {
+			let target_prop = prop(target);
+			for (k, term) in target_prop.terms.iter().enumerate() {
+				let mut positions = Vec::new();
+				subterm_positions(term, &mut vec![], &mut positions);
+				for position in positions {
+					let mut bindings = Bindings::new();
+					if bindings.unify(l, subterm_at(term, &position), OccursCheck::On).is_err() {
+						continue;
+					}
+					let (l_inst, r_inst) = (bindings.resolve(l), bindings.resolve(r));
+					if l_inst == r_inst || lpo_greater(&r_inst, &l_inst) {
+						continue;
+					}
+					let mut rewritten = target_prop.clone();
+					rewritten.terms[k] = replace_at(term, &position, r);
+					let rewritten = match target {
+						Literal::Proposition(_) => Literal::Proposition(rewritten),
+						_ => Literal::Not(rewritten),
+					};
+					let rest1 = from.literals.iter().enumerate().filter(|(m, _)| *m != i);
+					let rest2 = targets.iter().enumerate().filter(|(m, _)| *m != j);
+					let literals = rest1
+						.chain(rest2)
+						.map(|(_, lit)| lit.clone())
+						.chain(std::iter::once(rewritten));
+					paramodulants.push(instance(literals, &bindings));
+				}
+			}
+		}
+	}
+	paramodulants
+}
+
+/// The equality resolvents of `clause`: for each negative equation `s ≠ t` whose sides unify, the
+/// other literals under the unifier.
+fn equality_resolvents(clause: &NumberedClause) -> Vec<NumberedClause> {
+	let mut resolvents = Vec::new();
+	for (i, lit) in clause.literals.iter().enumerate() {
+		let Literal::Not(p) = lit else {
+			continue;
+		};
+		let Some((left, right)) = p.as_equation() else {
+			continue;
+		};
+		let mut bindings = Bindings::new();
+		if bindings.unify(left, right, OccursCheck::On).is_ok() {
+			let rest = clause.literals.iter().enumerate().filter(|(j, _)| *j != i);
+			resolvents.push(instance(rest.map(|(_, lit)| lit.clone()), &bindings));
+		}
+	}
+	resolvents
+}
+
+/// The equality factors of `clause`: for two positive equations `s = t` and `s' = t'` where `s`
+/// and `s'` unify, the clause with `s = t` replaced by `t ≠ t'`, under the unifier.
+fn equality_factors(clause: &NumberedClause) -> Vec<NumberedClause> {
+	let mut factors = Vec::new();
+	let equations = oriented_equations(&clause.literals);
+	for &(i, s, t) in &equations {
+		for &(j, s2, t2) in &equations {
+			if i == j {
+				continue;
+			}
+			let mut bindings = Bindings::new();
+			if bindings.unify(s, s2, OccursCheck::On).is_err() {
+				continue;
+			}
+			let rest = clause.literals.iter().enumerate().filter(|(k, _)| *k != i);
+			let disequation = Literal::Not(Proposition {
+				name: EQUALITY.to_string(),
+				terms: vec![t.clone(), t2.clone()],
+			});
+			let literals = rest.map(|(_, lit)| lit.clone()).chain(std::iter::once(disequation));
+			factors.push(instance(literals, &bindings));
+		}
+	}
+	factors
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::resolution::testing::clauses;
+
+	fn numbered(source: &str) -> NumberedClause {
+		let (clause, _) = clauses(source)[0].number_vars(0);
+		NumberedClause::new(clause.0)
+	}
+
+	#[test]
+	fn test_subsumption() {
+		let general = numbered("P(X, Y) or Q(X).");
+
+		assert!(general.subsumes(&numbered("P(a, b) or Q(a) or R(c).")));
+		assert!(general.subsumes(&numbered("P(Z, W) or Q(Z).")));
+		assert!(!general.subsumes(&numbered("P(a, b) or Q(b).")));
+		assert!(!general.subsumes(&numbered("P(a, b).")));
+		assert!(numbered("P(X, X).").subsumes(&numbered("P(a, a).")));
+		assert!(!numbered("P(X, X).").subsumes(&numbered("P(a, b).")));
+		// Two literals cannot both map onto `P(a)`.
+		assert!(!numbered("P(X) or P(Y).").subsumes(&numbered("P(a) or Q(b).")));
+		assert!(numbered("P(X) or P(Y).").subsumes(&numbered("P(a) or Q(b) or P(c).")));
+	}
+
+	#[test]
+	fn test_tautology() {
+		assert!(numbered("P(X) or (not P(X)) or Q(X).").is_tautology());
+		assert!(!numbered("P(X) or (not P(Y)).").is_tautology());
+	}
+
+	#[test]
+	fn test_resolution_and_factoring() {
+		let c1 = numbered("P(X) or Q(X).");
+		let c2 = numbered("(not P(a)) or R(Y).");
+		let resolvents: Vec<String> = resolvents(&c1, &c2).iter().map(|c| c.to_string()).collect();
+		assert_eq!(resolvents, vec!["{Q(a()), R(_G0)}"]);
+
+		let factors: Vec<String> = factors(&numbered("P(X) or P(a) or Q(X)."))
+			.iter()
+			.map(|c| c.to_string())
+			.collect();
+		assert_eq!(factors, vec!["{P(a()), Q(a())}"]);
+	}
+
+	#[test]
+	fn test_prove() {
+		// A non-Horn program: the goal only follows by case analysis.
+		let mut input =
+			clauses("P(a) or Q(a). forall X. (P(X) impl R(X)). forall X. (Q(X) impl R(X)).");
+		input.extend(clauses("not R(a)."));
+		assert_eq!(Prover::new(&input).unwrap().run(), ProofResult::Proved);
+		let program = &input[..3];
+		let prove = |negated_goal: &str| {
+			let input: Vec<Clause> = program.iter().cloned().chain(clauses(negated_goal)).collect();
+			Prover::new(&input).unwrap().run()
+		};
+		assert_eq!(prove("not (exists X. R(X))."), ProofResult::Proved);
+		assert_eq!(prove("not (P(a) or Q(a))."), ProofResult::Proved);
+		assert_eq!(prove("not P(a)."), ProofResult::CounterSatisfiable);
+
+		let mut input = clauses("P(a) or Q(a). forall X. (P(X) impl R(X)).");
+		input.extend(clauses("not R(a)."));
+		assert_eq!(Prover::new(&input).unwrap().run(), ProofResult::CounterSatisfiable);
+	}
+
+	#[test]
+	fn test_prove_iff_and_xor() {
+		let program = "Father(juan, pepe). Father(pepe, maria).
+			Grandpa(X, Y) if Father(X, Z) and Father(Z, Y).
+			Rich(juan) iff Grandpa(juan, maria).";
+		let mut input = clauses(program);
+		input.extend(clauses("not Rich(juan)."));
+		assert_eq!(Prover::new(&input).unwrap().run(), ProofResult::Proved);
+
+		let mut input = clauses(program);
+		input.extend(clauses("not (Rich(pepe) xor Rich(juan))."));
+		assert_eq!(Prover::new(&input).unwrap().run(), ProofResult::CounterSatisfiable);
+	}
+
+	#[test]
+	fn test_skolem_constants() {
+		// The witness is some individual, not every individual.
+		let mut input = clauses("exists X. Witness(X).");
+		input.extend(clauses("not Witness(b)."));
+		assert_eq!(Prover::new(&input).unwrap().run(), ProofResult::CounterSatisfiable);
+
+		let mut input = clauses("exists X. Witness(X).");
+		input.extend(clauses("not (exists Y. Witness(Y))."));
+		assert_eq!(Prover::new(&input).unwrap().run(), ProofResult::Proved);
+
+		let input = clauses("not (P(a) impl (forall X. P(X))).");
+		assert_eq!(Prover::new(&input).unwrap().run(), ProofResult::CounterSatisfiable);
+	}
+
+	#[test]
+	fn test_paramodulation() {
+		let from = numbered("f(a) = b.");
+		let into = numbered("P(f(X)).");
+		let conclusions: Vec<String> =
+			paramodulants(&from, &into).iter().map(|c| c.to_string()).collect();
+		assert_eq!(conclusions, vec!["{P(b())}"]);
+		// Equations are not used from their smaller side.
+		assert!(paramodulants(&from, &numbered("P(b).")).is_empty());
+
+		let resolvents = equality_resolvents(&numbered("(not f(X) = f(a)) or P(X)."));
+		assert_eq!(resolvents[0].to_string(), "{P(a())}");
+		assert!(numbered("X = X or P(X).").is_tautology());
+	}
+
+	#[test]
+	fn test_demodulation() {
+		let mut prover = Prover::new(&clauses("P(a).")).unwrap();
+		prover.active.push(numbered("f(X, e) = X."));
+		let clause = numbered("Q(Y, f(g(Y), e)).");
+		assert_eq!(prover.demodulate(&clause).to_string(), "{Q(_G0, g(_G0))}");
+
+		// The equation cannot be oriented, since `Y` does not occur in `g(X)`: rewriting with it
+		// would give `f` a variable that the clause already uses.
+		prover.active = vec![numbered("g(X) = f(Y).")];
+		let clause = numbered("P(W, g(h(Z))).");
+		assert_eq!(prover.demodulate(&clause), clause);
+	}
+
+	#[test]
+	fn test_prove_with_equality() {
+		let mut input = clauses("f(a) = b. P(b).");
+		input.extend(clauses("not P(f(a))."));
+		assert_eq!(Prover::new(&input).unwrap().run(), ProofResult::Proved);
+
+		let mut input = clauses("a = b. b = c.");
+		input.extend(clauses("not a = c."));
+		assert_eq!(Prover::new(&input).unwrap().run(), ProofResult::Proved);
+
+		// Group theory: a left inverse cancels on the left.
+		let group = "forall X. mul(e, X) = X. forall X. mul(inv(X), X) = e. \
+			forall X. forall Y. forall Z. mul(mul(X, Y), Z) = mul(X, mul(Y, Z)).";
+		let mut input = clauses(group);
+		input.extend(clauses("not mul(inv(a), mul(a, b)) = b."));
+		assert_eq!(Prover::new(&input).unwrap().run(), ProofResult::Proved);
+
+		let mut input = clauses("a = b.");
+		input.extend(clauses("not a = c."));
+		assert_eq!(Prover::new(&input).unwrap().run(), ProofResult::CounterSatisfiable);
+	}
+
+	#[test]
+	fn test_limit() {
+		let input = clauses("Nat(zero). forall X. (Nat(X) impl Nat(s(X))). not Nat(a).");
+		let limits = Limits { max_steps: Some(50), ..Limits::unbounded() };
+		let result = Prover::new(&input).unwrap().with_limits(limits).run();
+		assert_eq!(result, ProofResult::Unknown(LimitExceeded::Steps(50)));
+		assert_eq!(result.to_string(), "unknown (limit reached)");
+	}
+}
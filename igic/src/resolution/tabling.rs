@@ -0,0 +1,256 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::types::ast::{Proposition, Term};
+
+/// The answers of a tabled call, each one the arguments of the call once solved. Variables left
+/// unbound by an answer are named `_T0`, `_T1`, ... as in [`variant`].
+pub type TableAnswers = Vec<Vec<Term>>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TableState {
+	/// The call is being evaluated, at this position of the evaluation stack.
+	Evaluating(usize),
+	/// The call was evaluated, but used answers of a call that was still being evaluated, so it
+	/// has to be evaluated again the next time it is called.
+	Incomplete,
+	/// Every answer of the call is in the table.
+	Complete,
+}
+
+#[derive(Debug)]
+struct Table {
+	answers: TableAnswers,
+	seen: HashSet<Vec<Term>>,
+	state: TableState,
+}
+
+/// A call on the evaluation stack.
+#[derive(Debug)]
+struct Evaluation {
+	call: Proposition,
+	/// The lowest position of the stack whose answers the evaluation used so far.
+	lowest: usize,
+	/// The length of `incomplete` when the evaluation started.
+	incomplete_mark: usize,
+}
+
+/// The answer tables of the tabled predicates of a query, shared by the derivation and its
+/// sub-derivations.
+///
+/// A tabled call is evaluated by resolving it against the program clauses until no new answer is
+/// found, while calls to a variant of a call under evaluation only use the answers found so far
+/// instead of resolving again. Left-recursive and mutually recursive predicates therefore
+/// terminate, and each answer is returned once. Calls are told apart up to variable renaming, so
+/// `Path(a, X)` and `Path(a, Y)` share a table, but `Path(a, X)` and `Path(X, Y)` do not.
+///
+/// A group of calls that use each other's answers is completed together: only the oldest call of
+/// the group, its leader, iterates to a fixpoint, and the other calls are evaluated again in each
+/// of its iterations.
+#[derive(Debug, Default)]
+pub struct Tables {
+	tables: HashMap<Proposition, Table>,
+	evaluating: Vec<Evaluation>,
+	/// The incomplete calls evaluated under the calls on the stack, completed with their leader.
+	incomplete: Vec<Proposition>,
+	/// The number of answers in all the tables, which only grows.
+	answer_count: usize,
+}
+
+impl Tables {
+	pub fn new() -> Self {
+		Tables::default()
+	}
+
+	/// The answers to use for `call`, or `None` if it has to be evaluated. The answers of a call
+	/// under evaluation may not be all its answers yet, and using them makes the current
+	/// evaluation part of its group.
+	pub fn lookup(&mut self, call: &Proposition) -> Option<TableAnswers> {
+		let table = self.tables.get(call)?;
+		match table.state {
+			TableState::Complete => Some(table.answers.clone()),
+			TableState::Evaluating(position) => {
+				if let Some(top) = self.evaluating.last_mut() {
+					top.lowest = top.lowest.min(position);
+				}
+				Some(table.answers.clone())
+			},
+			TableState::Incomplete => None,
+		}
+	}
+
+	/// Starts evaluating `call`, creating its table if needed.
+	pub fn start(&mut self, call: Proposition) {
+		let position = self.evaluating.len();
+		let table = self.tables.entry(call.clone()).or_insert_with(|| Table {
+			answers: Vec::new(),
+			seen: HashSet::new(),
+			state: TableState::Incomplete,
+		});
+		table.state = TableState::Evaluating(position);
+		self.evaluating.push(Evaluation {
+			call,
+			lowest: position,
+			incomplete_mark: self.incomplete.len(),
+		});
+	}
+
+	/// Adds `answer` to the table of `call` if it is not a variant of an answer already there.
+	pub fn add_answer(&mut self, call: &Proposition, answer: &[Term]) {
+		let answer = variant(answer);
+		if let Some(table) = self.tables.get_mut(call) {
+			if table.seen.insert(answer.clone()) {
+				table.answers.push(answer);
+				self.answer_count += 1;
+			}
+		}
+	}
+
+	pub fn answer_count(&self) -> usize {
+		self.answer_count
+	}
+
+	/// Ends the innermost evaluation and returns the answers of its call. The table is complete if
+	/// the evaluation only used its own answers and those of later calls, unless it was `aborted`
+	/// before reaching a fixpoint.
+	pub fn finish(&mut self, aborted: bool) -> TableAnswers {
+		let Some(evaluation) = self.evaluating.pop() else {
+			return Vec::new();
+		};
+		let position = self.evaluating.len();
+		let leader = evaluation.lowest == position && !aborted;
+		if leader {
+			for call in self.incomplete.drain(evaluation.incomplete_mark..) {
+				if let Some(table) = self.tables.get_mut(&call) {
+					table.state = TableState::Complete;
+				}
+			}
+		} else {
+			if let Some(parent) = self.evaluating.last_mut() {
+				parent.lowest = parent.lowest.min(evaluation.lowest);
+			}
+			self.incomplete.push(evaluation.call.clone());
+		}
+
+		match self.tables.get_mut(&evaluation.call) {
+			Some(table) => {
+				table.state = if leader { TableState::Complete } else { TableState::Incomplete };
+				table.answers.clone()
+			},
+			None => Vec::new(),
+		}
+	}
+}
+
+/// Renames the variables of `terms` to `_T0`, `_T1`, ... in order of first appearance, so that
+/// two lists of terms that are equal up to variable renaming become equal.
+pub fn variant(terms: &[Term]) -> Vec<Term> {
+	fn rename(term: &Term, names: &mut HashMap<Term, usize>) -> Term {
+		match term {
+			Term::Var(_) | Term::Identifier(_) => {
+				let next = names.len();
+				let index = *names.entry(term.clone()).or_insert(next);
+				Term::Identifier(format!("_T{}", index))
+			},
+			Term::FunctionApplication { name, args } => Term::FunctionApplication {
+				name: name.clone(),
+				args: args.iter().map(|arg| rename(arg, names)).collect(),
+			},
+			Term::Number(_) => term.clone(),
+		}
+	}
+	let mut names = HashMap::new();
+	terms.iter().map(|t| rename(t, &mut names)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::libraries::standard_builtins;
+	use crate::resolution::testing::{answers, program, solve};
+
+	fn call(name: &str, terms: Vec<Term>) -> Proposition {
+		Proposition { name: name.to_string(), terms: variant(&terms) }
+	}
+
+	fn constant(name: &str) -> Term {
+		Term::FunctionApplication { name: name.to_string(), args: vec![] }
+	}
+
+	#[test]
+	fn test_variant() {
+		let t1 = variant(&[Term::Var(4), constant("a"), Term::Var(4), Term::Var(1)]);
+		let t2 = variant(&[Term::Var(0), constant("a"), Term::Var(0), Term::Var(7)]);
+
+		assert_eq!(t1, t2);
+		assert_eq!(t1[3], Term::Identifier("_T1".to_string()));
+		assert_ne!(t1, variant(&[Term::Var(0), constant("a"), Term::Var(1), Term::Var(1)]));
+	}
+
+	#[test]
+	fn test_answers_are_added_once() {
+		let mut tables = Tables::new();
+		let path = call("Path", vec![constant("a"), Term::Var(0)]);
+
+		tables.start(path.clone());
+		tables.add_answer(&path, &[constant("a"), constant("b")]);
+		tables.add_answer(&path, &[constant("a"), constant("b")]);
+		tables.add_answer(&path, &[constant("a"), Term::Var(3)]);
+		tables.add_answer(&path, &[constant("a"), Term::Var(5)]);
+
+		assert_eq!(tables.answer_count(), 2);
+		assert_eq!(tables.finish(false).len(), 2);
+		assert!(tables.lookup(&path).is_some());
+	}
+
+	#[test]
+	fn test_group_completes_with_its_leader() {
+		let mut tables = Tables::new();
+		let p = call("P", vec![Term::Var(0)]);
+		let q = call("Q", vec![Term::Var(0)]);
+
+		tables.start(p.clone());
+		assert!(tables.lookup(&q).is_none());
+		tables.start(q.clone());
+		// Q uses the answers of P, which is still being evaluated.
+		assert!(tables.lookup(&p).is_some());
+		tables.finish(false);
+		assert!(tables.lookup(&q).is_none());
+
+		tables.finish(false);
+		assert!(tables.lookup(&p).is_some());
+		assert!(tables.lookup(&q).is_some());
+	}
+
+	#[test]
+	fn test_left_recursion() {
+		let program = program(
+			":- table Path/2.
+			Edge(a, b). Edge(b, c). Edge(c, a). Edge(c, d).
+			forall X. forall Y. (Edge(X, Y) => Path(X, Y)).
+			forall X. forall Y. forall Z. ((Path(X, Z) and Edge(Z, Y)) => Path(X, Y)).",
+		);
+		let builtins = standard_builtins();
+
+		let mut path_from_a = answers(solve(&program, &builtins, "Path(a, Y)"));
+		path_from_a.sort();
+		assert_eq!(path_from_a, vec!["Y := a()", "Y := b()", "Y := c()", "Y := d()"]);
+		assert_eq!(solve(&program, &builtins, "Path(X, Y)").count(), 12);
+		assert_eq!(solve(&program, &builtins, "Path(d, Y)").count(), 0);
+	}
+
+	#[test]
+	fn test_mutual_recursion() {
+		let program = program(
+			":- table Even/1, Odd/1.
+			Even(zero).
+			forall X. (Odd(X) => Even(s(X))).
+			forall X. (Even(X) => Odd(s(X))).
+			forall X. (Even(X) => Loop(X)).
+			forall X. (Loop(X) => Even(X)).",
+		);
+		let builtins = standard_builtins();
+
+		assert_eq!(answers(solve(&program, &builtins, "Even(s(s(zero)))")), vec!["true."]);
+		assert_eq!(solve(&program, &builtins, "Odd(s(s(zero)))").count(), 0);
+	}
+}
@@ -0,0 +1,50 @@
+//! Fixtures shared by the unit tests that run programs written in GIC.
+
+use crate::clauses::cnf::Clausifier;
+use crate::engine::LIBRARIES;
+use crate::libraries::BuiltinRegistry;
+use crate::parser::{parse_formula, parse_gic_program};
+use crate::resolution::answer::Answer;
+use crate::resolution::resolution::{sld_resolution, Solver};
+use crate::types::ast::{Expression, Statement};
+use crate::types::clause::{Clause, Program};
+
+/// Clausifies the formulas of `source`, along with its `:- table` declarations.
+pub fn program(source: &str) -> Program {
+	let mut clausifier = Clausifier::new();
+	for statement in parse_gic_program(source).unwrap() {
+		match statement {
+			Statement::Formula(expr) => clausifier.add_to_program(expr).unwrap(),
+			Statement::Table(preds) => {
+				for (name, arity) in preds {
+					clausifier.table(&name, arity);
+				}
+			},
+		}
+	}
+	clausifier.get_program().clone()
+}
+
+/// The clauses of the formulas of `source`.
+pub fn clauses(source: &str) -> Vec<Clause> {
+	program(source).clauses().to_vec()
+}
+
+/// Clausifies `source` after the standard libraries written in GIC, as [`crate::Engine::new`]
+/// loads them.
+pub fn program_with_libraries(source: &str) -> Program {
+	let libraries: String = LIBRARIES.iter().map(|(_, content)| *content).collect();
+	program(&(libraries + source))
+}
+
+/// Starts the SLD resolution of `query` against `program`, as [`crate::Engine::query`] does.
+pub fn solve<'p>(program: &'p Program, builtins: &'p BuiltinRegistry, query: &str) -> Solver<'p> {
+	let negated = Expression::Not(Box::new(parse_formula(&format!("{};", query)).unwrap()));
+	let goal: Clause = Clausifier::new().clausify(negated).unwrap().get_clause(0).cloned().unwrap();
+	sld_resolution(program, builtins, &goal).unwrap()
+}
+
+/// Formats each answer found by `solver`.
+pub fn answers(solver: impl Iterator<Item = Answer>) -> Vec<String> {
+	solver.map(|a| a.to_string()).collect()
+}
@@ -1,3 +1,4 @@
+use std::collections::HashMap;
 use std::fmt;
 
 use crate::mgu::mgu::Unifiable;
@@ -5,8 +6,14 @@ use crate::mgu::mgu::Unifiable;
 #[derive(Debug, PartialEq, Clone, Eq, Hash)]
 pub enum Term {
 	Identifier(String),
-	FunctionApplication { name: String, args: Vec<Term> },
+	FunctionApplication {
+		name: String,
+		args: Vec<Term>,
+	},
 	Number(i64),
+	/// A variable numbered by the solver when renaming clauses apart. Only appears during
+	/// resolution, never in parsed formulas.
+	Var(usize),
 }
 
 impl Term {
@@ -30,17 +37,32 @@ impl Term {
 		}
 	}
 
-	pub fn append_suffix_to_vars(&self, suffix: &str) -> Term {
-		let new_term = match self {
-			Term::Identifier(id) => Term::Identifier(format!("{}{}", id, suffix)),
-			Term::FunctionApplication { name, args } => {
-				let new_args: Vec<Term> =
-					args.iter().map(|arg| arg.append_suffix_to_vars(suffix)).collect();
-				Term::FunctionApplication { name: name.clone(), args: new_args }
+	/// Returns true if the term has no variables, named or numbered.
+	pub fn is_ground(&self) -> bool {
+		match self {
+			Term::Identifier(_) | Term::Var(_) => false,
+			Term::FunctionApplication { args, .. } => args.iter().all(Term::is_ground),
+			Term::Number(_) => true,
+		}
+	}
+
+	/// Replaces each named variable by `Term::Var(first + i)`, where `i` is the index of its name
+	/// in `names`. Names not seen before are added to `names` with the next index, so numbering
+	/// the literals of a clause one after the other with the same `names` renames the whole clause
+	/// apart.
+	pub fn number_vars<'a>(&'a self, first: usize, names: &mut HashMap<&'a str, usize>) -> Term {
+		match self {
+			Term::Identifier(id) => {
+				let next = names.len();
+				Term::Var(first + *names.entry(id).or_insert(next))
+			},
+			Term::FunctionApplication { name, args } => Term::FunctionApplication {
+				name: name.clone(),
+				args: args.iter().map(|arg| arg.number_vars(first, names)).collect(),
 			},
 			Term::Number(n) => Term::Number(*n),
-		};
-		new_term
+			Term::Var(id) => Term::Var(*id),
+		}
 	}
 }
 
@@ -78,18 +100,50 @@ impl fmt::Display for Term {
 				}
 			},
 			Term::Number(n) => write!(f, "{}", n),
+			Term::Var(id) => write!(f, "_G{}", id),
 		}
 	}
 }
 
+/// The name of the equality predicate, written `t1 = t2`.
+pub const EQUALITY: &str = "=";
+
 #[derive(Debug, PartialEq, Clone, Eq, Hash)]
 pub struct Proposition {
 	pub name: String,
 	pub terms: Vec<Term>,
 }
 
+impl Proposition {
+	/// Numbers the variables of every term, see [`Term::number_vars`].
+	pub fn number_vars<'a>(
+		&'a self,
+		first: usize,
+		names: &mut HashMap<&'a str, usize>,
+	) -> Proposition {
+		Proposition {
+			name: self.name.clone(),
+			terms: self.terms.iter().map(|t| t.number_vars(first, names)).collect(),
+		}
+	}
+
+	/// The two sides of an equation `t1 = t2`, or `None` for any other proposition.
+	pub fn as_equation(&self) -> Option<(&Term, &Term)> {
+		match self.terms.as_slice() {
+			[left, right] if self.name == EQUALITY => Some((left, right)),
+			_ => None,
+		}
+	}
+}
+
 impl fmt::Display for Proposition {
 	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		if let Some((left, right)) = self.as_equation() {
+			return write!(f, "{} = {}", left, right);
+		}
+		if self.terms.is_empty() {
+			return write!(f, "{}", self.name);
+		}
 		let terms_str: Vec<String> = self
 			.terms //
 			.iter()
@@ -103,14 +157,48 @@ impl fmt::Display for Proposition {
 pub enum Expression {
 	Proposition(Proposition),
 	Bottom,
+	Top,
 	And(Box<Expression>, Box<Expression>),
 	Or(Box<Expression>, Box<Expression>),
+	Xor(Box<Expression>, Box<Expression>),
 	Implies(Box<Expression>, Box<Expression>),
+	/// Reverse implication, `A <= B` or `A if B`, which is `B => A`.
+	ImpliedBy(Box<Expression>, Box<Expression>),
+	Iff(Box<Expression>, Box<Expression>),
 	Not(Box<Expression>),
+	/// Negation as failure, `\+ G`: holds if the goal `G`, a conjunction of atoms, has no
+	/// solution. Only allowed where SLD resolution calls goals, i.e. in rule bodies and queries.
+	NegationAsFailure(Box<Expression>),
 	Exists(String, Box<Expression>),
 	ForAll(String, Box<Expression>),
 }
 
+impl Expression {
+	/// Returns true if the variable `var` occurs in the formula outside the scope of a quantifier
+	/// over `var`.
+	pub fn has_free_var(&self, var: &str) -> bool {
+		fn in_term(term: &Term, var: &str) -> bool {
+			match term {
+				Term::Identifier(id) => id == var,
+				Term::FunctionApplication { args, .. } => args.iter().any(|arg| in_term(arg, var)),
+				Term::Number(_) | Term::Var(_) => false,
+			}
+		}
+		match self {
+			Expression::Proposition(p) => p.terms.iter().any(|term| in_term(term, var)),
+			Expression::Bottom | Expression::Top => false,
+			Expression::And(a, b)
+			| Expression::Or(a, b)
+			| Expression::Xor(a, b)
+			| Expression::Implies(a, b)
+			| Expression::ImpliedBy(a, b)
+			| Expression::Iff(a, b) => a.has_free_var(var) || b.has_free_var(var),
+			Expression::Not(e) | Expression::NegationAsFailure(e) => e.has_free_var(var),
+			Expression::Exists(v, e) | Expression::ForAll(v, e) => v != var && e.has_free_var(var),
+		}
+	}
+}
+
 impl fmt::Display for Expression {
 	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
 		match self {
@@ -118,12 +206,27 @@ impl fmt::Display for Expression {
 				write!(f, "{}", prop)
 			},
 			Expression::Bottom => write!(f, "⊥"),
+			Expression::Top => write!(f, "⊤"),
 			Expression::And(left, right) => write!(f, "({} ∧ {})", left, right),
 			Expression::Or(left, right) => write!(f, "({} ∨ {})", left, right),
+			Expression::Xor(left, right) => write!(f, "({} ⊕ {})", left, right),
 			Expression::Implies(left, right) => write!(f, "({} => {})", left, right),
+			Expression::ImpliedBy(left, right) => write!(f, "({} <= {})", left, right),
+			Expression::Iff(left, right) => write!(f, "({} <=> {})", left, right),
 			Expression::Not(expr) => write!(f, "¬{}", expr),
+			Expression::NegationAsFailure(expr) => write!(f, "\\+{}", expr),
 			Expression::Exists(var, expr) => write!(f, "∃{}: {}", var, expr),
 			Expression::ForAll(var, expr) => write!(f, "∀{}: {}", var, expr),
 		}
 	}
 }
+
+/// A top-level item of a .gic file.
+#[derive(Debug, PartialEq, Clone)]
+pub enum Statement {
+	/// A formula, added to the program.
+	Formula(Expression),
+	/// A `:- table P/n, ...` directive, listing the predicates to answer with tabling by name and
+	/// arity.
+	Table(Vec<(String, usize)>),
+}
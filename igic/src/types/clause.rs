@@ -1,4 +1,6 @@
 use crate::types::ast::{Proposition, Term};
+use crate::types::index::ClauseIndex;
+use std::collections::{HashMap, HashSet};
 use std::fmt;
 use std::slice::Iter;
 
@@ -6,6 +8,9 @@ use std::slice::Iter;
 pub enum Literal {
 	Proposition(Proposition),
 	Not(Proposition),
+	/// A call to negation as failure, `\+ G`, in the body of a rule or in a query. The goal `G`
+	/// is kept as a goal clause, and the literal holds if it has no solution.
+	NegationAsFailure(Clause),
 }
 
 impl Literal {
@@ -16,27 +21,35 @@ impl Literal {
 	pub fn is_negative(&self) -> bool {
 		!self.is_positive()
 	}
+
+	pub fn is_ground(&self) -> bool {
+		match self {
+			Literal::Proposition(prop) | Literal::Not(prop) => {
+				prop.terms.iter().all(Term::is_ground)
+			},
+			Literal::NegationAsFailure(goal) => goal.iter().all(Literal::is_ground),
+		}
+	}
+
+	/// Numbers the variables of the literal, see [`Term::number_vars`].
+	pub fn number_vars<'a>(&'a self, first: usize, names: &mut HashMap<&'a str, usize>) -> Literal {
+		match self {
+			Literal::Proposition(prop) => Literal::Proposition(prop.number_vars(first, names)),
+			Literal::Not(prop) => Literal::Not(prop.number_vars(first, names)),
+			Literal::NegationAsFailure(goal) => Literal::NegationAsFailure(Clause(
+				goal.iter().map(|lit| lit.number_vars(first, names)).collect(),
+			)),
+		}
+	}
 }
 
 impl fmt::Display for Literal {
 	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
 		match self {
-			Literal::Proposition(prop) => {
-				write!(
-					f,
-					"{}({})",
-					prop.name,
-					prop.terms.iter().map(|t| format!("{}", t)).collect::<Vec<String>>().join(", ")
-				)
-			},
-			Literal::Not(prop) => {
-				write!(
-					f,
-					"¬{}({})",
-					prop.name,
-					prop.terms.iter().map(|t| format!("{}", t)).collect::<Vec<String>>().join(", ")
-				)
-			},
+			Literal::Proposition(prop) => write!(f, "{}", prop),
+			Literal::Not(prop) if prop.as_equation().is_some() => write!(f, "¬({})", prop),
+			Literal::Not(prop) => write!(f, "¬{}", prop),
+			Literal::NegationAsFailure(goal) => write!(f, "¬\\+({})", goal.goal_str()),
 		}
 	}
 }
@@ -44,6 +57,12 @@ impl fmt::Display for Literal {
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct Clause(pub Vec<Literal>);
 
+impl Default for Clause {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
 impl Clause {
 	pub fn new() -> Self {
 		Clause(vec![])
@@ -66,7 +85,7 @@ impl Clause {
 	}
 
 	pub fn negatives(&self) -> Vec<&Literal> {
-		self.0.iter().filter(|lit| matches!(lit, Literal::Not(_))).collect()
+		self.0.iter().filter(|lit| lit.is_negative()).collect()
 	}
 
 	pub fn len(&self) -> usize {
@@ -86,6 +105,13 @@ impl Clause {
 		let mut free_vars = Vec::new();
 		for lit in &self.0 {
 			match lit {
+				Literal::NegationAsFailure(goal) => {
+					for id in goal.fv() {
+						if !free_vars.contains(&id) {
+							free_vars.push(id);
+						}
+					}
+				},
 				Literal::Proposition(prop) => {
 					for term in &prop.terms {
 						if let Term::Identifier(ref id) = term {
@@ -109,25 +135,27 @@ impl Clause {
 		free_vars
 	}
 
-	pub fn suffix_vars(&self, suffix: &str) -> Clause {
-		let new_lits = self
+	/// The clause read as a goal: its negative literals as calls, separated by commas.
+	pub fn goal_str(&self) -> String {
+		let goals: Vec<String> = self
 			.0
 			.iter()
 			.map(|lit| match lit {
-				Literal::Proposition(prop) => {
-					let new_terms =
-						prop.terms.iter().map(|t| t.append_suffix_to_vars(suffix)).collect();
-					Literal::Proposition(Proposition { name: prop.name.clone(), terms: new_terms })
-				},
-				Literal::Not(prop) => {
-					let new_terms =
-						prop.terms.iter().map(|t| t.append_suffix_to_vars(suffix)).collect();
-					Literal::Not(Proposition { name: prop.name.clone(), terms: new_terms })
-				},
+				Literal::Not(prop) => prop.to_string(),
+				Literal::NegationAsFailure(goal) => format!("\\+({})", goal.goal_str()),
+				Literal::Proposition(_) => lit.to_string(),
 			})
 			.collect();
+		goals.join(", ")
+	}
 
-		Clause(new_lits)
+	/// Renames the clause apart by numbering its variables from `first` on, in order of first
+	/// appearance. Returns the renamed clause and the index of each variable name, the variable
+	/// numbered `first + i` being the one with index `i`.
+	pub fn number_vars(&self, first: usize) -> (Clause, HashMap<&str, usize>) {
+		let mut names = HashMap::new();
+		let lits = self.0.iter().map(|lit| lit.number_vars(first, &mut names)).collect();
+		(Clause(lits), names)
 	}
 }
 
@@ -162,20 +190,60 @@ impl fmt::Display for Clause {
 	}
 }
 
-#[derive(Debug, Clone, PartialEq)]
-pub struct Program(pub Vec<Clause>);
+/// A list of clauses, indexed by predicate and first argument as they are added.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Program {
+	clauses: Vec<Clause>,
+	index: ClauseIndex,
+	/// The predicates declared with `:- table`, by name and arity.
+	tabled: HashMap<String, HashSet<usize>>,
+}
 
 impl Program {
+	pub fn new() -> Self {
+		Program::default()
+	}
+
+	pub fn clauses(&self) -> &[Clause] {
+		&self.clauses
+	}
+
 	pub fn get_clause(&self, index: usize) -> Option<&Clause> {
-		self.0.get(index)
+		self.clauses.get(index)
+	}
+
+	pub fn push(&mut self, clause: Clause) {
+		self.index.add(self.clauses.len(), &clause);
+		self.clauses.push(clause);
 	}
 
 	pub fn append(&mut self, other: &mut Self) {
-		self.0.append(&mut other.0);
+		for clause in other.clauses.drain(..) {
+			self.push(clause);
+		}
+		other.index = ClauseIndex::default();
+		for (name, arities) in other.tabled.drain() {
+			self.tabled.entry(name).or_default().extend(arities);
+		}
+	}
+
+	/// The positions of the clauses whose head may unify with a call to `name/arity` whose first
+	/// argument is `first_arg`, in program order. See [`ClauseIndex::candidates`].
+	pub fn candidates(&self, name: &str, arity: usize, first_arg: Option<&Term>) -> &[usize] {
+		self.index.candidates(name, arity, first_arg)
+	}
+
+	/// Declares that calls to `name/arity` are answered with tabling.
+	pub fn table(&mut self, name: &str, arity: usize) {
+		self.tabled.entry(name.to_string()).or_default().insert(arity);
+	}
+
+	pub fn is_tabled(&self, name: &str, arity: usize) -> bool {
+		self.tabled.get(name).is_some_and(|arities| arities.contains(&arity))
 	}
 
 	pub fn is_horn(&self) -> bool {
-		self.0.iter().all(|clause| {
+		self.clauses.iter().all(|clause| {
 			let positive_count =
 				clause.iter().filter(|lit| matches!(lit, Literal::Proposition(_))).count();
 			positive_count <= 1 // At most one positive literal
@@ -183,28 +251,38 @@ impl Program {
 	}
 
 	pub fn is_empty(&self) -> bool {
-		self.0.is_empty()
+		self.clauses.is_empty()
 	}
 
 	pub fn get_progam_length(&self) -> usize {
-		self.0.len()
+		self.clauses.len()
 	}
 
 	pub fn str_from(&self, start: usize) -> String {
 		let mut output = String::new();
-		for (i, clause) in self.0.iter().enumerate().skip(start) {
+		for (i, clause) in self.clauses.iter().enumerate().skip(start) {
 			output.push_str(&format!("{}: {}\n", (i + 1) - start, clause));
 		}
 		output
 	}
 }
 
+impl From<Vec<Clause>> for Program {
+	fn from(clauses: Vec<Clause>) -> Self {
+		let mut program = Program::new();
+		for clause in clauses {
+			program.push(clause);
+		}
+		program
+	}
+}
+
 impl IntoIterator for Program {
 	type Item = Clause;
 	type IntoIter = std::vec::IntoIter<Clause>;
 
 	fn into_iter(self) -> Self::IntoIter {
-		self.0.into_iter()
+		self.clauses.into_iter()
 	}
 }
 
@@ -213,14 +291,14 @@ impl<'a> IntoIterator for &'a Program {
 	type IntoIter = std::slice::Iter<'a, Clause>;
 
 	fn into_iter(self) -> Self::IntoIter {
-		self.0.iter()
+		self.clauses.iter()
 	}
 }
 
 impl fmt::Display for Program {
 	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
 		writeln!(f, "{{")?;
-		for clause in &self.0 {
+		for clause in &self.clauses {
 			writeln!(f, "  {},", clause)?; // Each clause in braces + comma
 		}
 		writeln!(f, "}}")
@@ -6,10 +6,40 @@ use std::fmt::Debug;
 use std::hash::Hash;
 
 #[derive(Debug)]
+#[allow(clippy::enum_variant_names)]
 pub enum GicError {
 	ParseError(String),
 	SemanticError(String),
 	ClauseError(String),
+	ResolutionError(String),
+	IoError(String),
+	/// The operation was stopped by an interrupt flag.
+	Interrupted,
+}
+
+impl GicError {
+	/// A short, stable name for the kind of error, used in machine-readable output.
+	pub fn kind(&self) -> &'static str {
+		match self {
+			GicError::ParseError(_) => "parse",
+			GicError::SemanticError(_) => "semantic",
+			GicError::ClauseError(_) => "clause",
+			GicError::ResolutionError(_) => "resolution",
+			GicError::IoError(_) => "io",
+			GicError::Interrupted => "interrupted",
+		}
+	}
+
+	pub fn message(&self) -> &str {
+		match self {
+			GicError::ParseError(msg)
+			| GicError::SemanticError(msg)
+			| GicError::ClauseError(msg)
+			| GicError::ResolutionError(msg)
+			| GicError::IoError(msg) => msg,
+			GicError::Interrupted => "Interrupted.",
+		}
+	}
 }
 
 impl<T> From<pest::error::Error<T>> for GicError
@@ -23,7 +53,7 @@ where
 
 impl From<std::io::Error> for GicError {
 	fn from(error: std::io::Error) -> Self {
-		GicError::ParseError(error.to_string())
+		GicError::IoError(error.to_string())
 	}
 }
 
@@ -33,12 +63,17 @@ impl From<PrattError<Pair<'_, Rule>, GicError>> for GicError {
 	}
 }
 
+impl std::error::Error for GicError {}
+
 impl fmt::Display for GicError {
 	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
 		match self {
 			GicError::ParseError(msg) => write!(f, "Parse error: {}", msg),
 			GicError::SemanticError(msg) => write!(f, "Semantic error: {}", msg),
 			GicError::ClauseError(msg) => write!(f, "Clause error: {}", msg),
+			GicError::ResolutionError(msg) => write!(f, "Resolution error: {}", msg),
+			GicError::IoError(msg) => write!(f, "I/O error: {}", msg),
+			GicError::Interrupted => write!(f, "Interrupted."),
 		}
 	}
 }
@@ -0,0 +1,129 @@
+use std::collections::HashMap;
+
+use crate::types::ast::Term;
+use crate::types::clause::{Clause, Literal};
+
+/// The principal functor of a first argument, used to tell apart the clauses of a predicate.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum IndexKey {
+	Functor(String, usize),
+	Number(i64),
+}
+
+impl IndexKey {
+	/// The key of `term`, or `None` if it is a variable and so matches any key.
+	fn of(term: &Term) -> Option<IndexKey> {
+		match term {
+			Term::FunctionApplication { name, args } => {
+				Some(IndexKey::Functor(name.clone(), args.len()))
+			},
+			Term::Number(n) => Some(IndexKey::Number(*n)),
+			Term::Identifier(_) | Term::Var(_) => None,
+		}
+	}
+}
+
+/// The clauses of a single predicate, by position in the program.
+#[derive(Debug, Clone, Default, PartialEq)]
+struct PredicateIndex {
+	/// Every clause of the predicate.
+	all: Vec<usize>,
+	/// The clauses whose first argument is a variable.
+	open: Vec<usize>,
+	/// For each first-argument key, the clauses with that key or a variable first argument.
+	by_key: HashMap<IndexKey, Vec<usize>>,
+}
+
+/// An index of the clauses of a program by the name and arity of their head, and by the principal
+/// functor of the first argument of the head.
+///
+/// Every list of candidates keeps the clauses in program order, so that indexing does not change
+/// the order in which SLD resolution tries them.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ClauseIndex {
+	/// The predicates by name, then by arity.
+	preds: HashMap<String, HashMap<usize, PredicateIndex>>,
+}
+
+impl ClauseIndex {
+	/// Adds `clause`, at position `position` of the program. Clauses must be added in order.
+	/// Clauses without a positive first literal are not indexed, as they cannot resolve a goal.
+	pub fn add(&mut self, position: usize, clause: &Clause) {
+		let Some(Literal::Proposition(head)) = clause.0.first() else {
+			return;
+		};
+		let pred = self
+			.preds
+			.entry(head.name.clone())
+			.or_default()
+			.entry(head.terms.len())
+			.or_default();
+		pred.all.push(position);
+
+		match head.terms.first().and_then(IndexKey::of) {
+			Some(key) => {
+				let open = &pred.open;
+				pred.by_key.entry(key).or_insert_with(|| open.clone()).push(position);
+			},
+			None => {
+				pred.open.push(position);
+				for clauses in pred.by_key.values_mut() {
+					clauses.push(position);
+				}
+			},
+		}
+	}
+
+	/// The positions of the clauses whose head may unify with a call to `name/arity` whose first
+	/// argument is `first_arg`, which must already be dereferenced.
+	pub fn candidates(&self, name: &str, arity: usize, first_arg: Option<&Term>) -> &[usize] {
+		let Some(pred) = self.preds.get(name).and_then(|arities| arities.get(&arity)) else {
+			return &[];
+		};
+		match first_arg.and_then(IndexKey::of) {
+			Some(key) => pred.by_key.get(&key).unwrap_or(&pred.open),
+			None => &pred.all,
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::resolution::testing::program;
+
+	fn index(source: &str) -> ClauseIndex {
+		let mut index = ClauseIndex::default();
+		for (position, clause) in program(source).clauses().iter().enumerate() {
+			index.add(position, clause);
+		}
+		index
+	}
+
+	fn constant(name: &str) -> Term {
+		Term::FunctionApplication { name: name.to_string(), args: vec![] }
+	}
+
+	#[test]
+	fn test_predicate_index() {
+		let index = index("Father(juan, pepe). Mother(ana, pepe). Father(pepe, maria).");
+
+		assert_eq!(index.candidates("Father", 2, None), &[0, 2]);
+		assert_eq!(index.candidates("Mother", 2, None), &[1]);
+		assert!(index.candidates("Father", 1, None).is_empty());
+		assert!(index.candidates("Brother", 2, None).is_empty());
+	}
+
+	#[test]
+	fn test_first_argument_index() {
+		let index = index("P(a, 1). P(X, 2). P(b, 3). P(a, 4). P(f(Y), 5). P(7, 6).");
+
+		assert_eq!(index.candidates("P", 2, Some(&constant("a"))), &[0, 1, 3]);
+		assert_eq!(index.candidates("P", 2, Some(&constant("b"))), &[1, 2]);
+		assert_eq!(index.candidates("P", 2, Some(&constant("c"))), &[1]);
+		assert_eq!(index.candidates("P", 2, Some(&Term::Number(7))), &[1, 5]);
+		assert_eq!(index.candidates("P", 2, Some(&Term::Var(0))), &[0, 1, 2, 3, 4, 5]);
+		let f_a = Term::FunctionApplication { name: "f".to_string(), args: vec![constant("a")] };
+		assert_eq!(index.candidates("P", 2, Some(&f_a)), &[1, 4]);
+	}
+}
@@ -1,6 +1,7 @@
 pub mod ast;
 pub mod clause;
 pub mod errors;
+pub mod index;
 
 pub use errors::GicError;
 